@@ -0,0 +1,37 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use lights_out_solver::linalg::Matrix;
+use lights_out_solver::puzzle::{Puzzle, SolveMode};
+use std::hint::black_box;
+
+fn bench_rref(c: &mut Criterion) {
+    let mut group = c.benchmark_group("to_rref");
+    for (size, modulus) in [(16, 2), (25, 2), (100, 2), (25, 7)] {
+        let matrix = Matrix::random(size, size, modulus, 0);
+        group.bench_with_input(
+            BenchmarkId::new(format!("GF({})", modulus), format!("{}x{}", size, size)),
+            &matrix,
+            |b, matrix| b.iter(|| black_box(matrix).to_rref()),
+        );
+    }
+    group.finish();
+}
+
+fn bench_solve(c: &mut Criterion) {
+    // Chasing the lights against the matrix solver on standard boards
+    let mut group = c.benchmark_group("solve");
+    for size in [5, 10, 20] {
+        let initial = (0..size * size).map(|idx| idx % 3 == 0).collect();
+        let puzzle = Puzzle::new(size, size, initial);
+        let name = format!("{}x{}", size, size);
+        group.bench_with_input(BenchmarkId::new("chase", &name), &puzzle, |b, puzzle| {
+            b.iter(|| black_box(puzzle).solve_chase())
+        });
+        group.bench_with_input(BenchmarkId::new("matrix", &name), &puzzle, |b, puzzle| {
+            b.iter(|| black_box(puzzle).solve_with(SolveMode::Any))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_rref, bench_solve);
+criterion_main!(benches);
@@ -0,0 +1,189 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LinAlgError {
+    // Errors raised by fallible matrix operations
+    DimensionMismatch { expected: usize, found: usize },
+    Empty,
+    ModulusMismatch { expected: i32, found: i32 },
+    NotSquare { n_rows: usize, n_cols: usize },
+    OutOfBounds { range: Range<usize>, len: usize },
+}
+
+impl fmt::Display for LinAlgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinAlgError::DimensionMismatch { expected, found } => {
+                write!(
+                    f,
+                    "Dimension mismatch: expected {}, found {}",
+                    expected, found
+                )
+            }
+            LinAlgError::Empty => write!(f, "Matrix is empty"),
+            LinAlgError::ModulusMismatch { expected, found } => {
+                write!(
+                    f,
+                    "Modulus mismatch: expected {}, found {}",
+                    expected, found
+                )
+            }
+            LinAlgError::NotSquare { n_rows, n_cols } => {
+                write!(f, "Matrix is not square: {}x{}", n_rows, n_cols)
+            }
+            LinAlgError::OutOfBounds { range, len } => {
+                write!(f, "Range {:?} is out of bounds for length {}", range, len)
+            }
+        }
+    }
+}
+
+impl Error for LinAlgError {}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum GFError {
+    // Errors raised by fallible finite field operations
+    InvalidModulus(i32),
+    OutOfRange { value: i32, modulus: i32 },
+    DivisionByZero,
+    ModulusMismatch { expected: i32, found: i32 },
+    NotInvertible { value: i32, modulus: i32 },
+}
+
+impl fmt::Display for GFError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GFError::InvalidModulus(modulus) => {
+                write!(f, "Invalid modulus {}, expected at least 2", modulus)
+            }
+            GFError::OutOfRange { value, modulus } => {
+                write!(f, "Value {} is out of range [0, {})", value, modulus)
+            }
+            GFError::DivisionByZero => write!(f, "Division by 0 attempted"),
+            GFError::ModulusMismatch { expected, found } => {
+                write!(
+                    f,
+                    "Modulus mismatch: expected {}, found {}",
+                    expected, found
+                )
+            }
+            GFError::NotInvertible { value, modulus } => {
+                write!(f, "Value {} has no inverse modulo {}", value, modulus)
+            }
+        }
+    }
+}
+
+impl Error for GFError {}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseError {
+    // Errors raised while parsing matrices and puzzles from text.
+    // Rows and columns are counted from 0.
+    Empty,
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidCell {
+        row: usize,
+        col: usize,
+        found: char,
+    },
+    InvalidNumber {
+        row: usize,
+        col: usize,
+        found: String,
+    },
+    InvalidElement(String),
+    InvalidModulus(GFError),
+    // Reading the input failed, holds the message of the io::Error
+    Io(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "Input is empty"),
+            ParseError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Row {} has {} entries, expected {}",
+                row, found, expected
+            ),
+            ParseError::InvalidCell { row, col, found } => {
+                write!(f, "Invalid cell '{}' at row {}, column {}", found, row, col)
+            }
+            ParseError::InvalidNumber { row, col, found } => write!(
+                f,
+                "Invalid number '{}' at row {}, column {}",
+                found, row, col
+            ),
+            ParseError::InvalidElement(found) => write!(f, "Invalid element '{}'", found),
+            ParseError::InvalidModulus(err) => write!(f, "{}", err),
+            ParseError::Io(msg) => write!(f, "Failed to read input: {}", msg),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::InvalidModulus(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<GFError> for ParseError {
+    fn from(err: GFError) -> Self {
+        ParseError::InvalidModulus(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GFError, LinAlgError, ParseError};
+    use std::error::Error;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            LinAlgError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            }
+            .to_string(),
+            "Dimension mismatch: expected 2, found 3"
+        );
+        assert_eq!(
+            GFError::InvalidModulus(1).to_string(),
+            "Invalid modulus 1, expected at least 2"
+        );
+        assert_eq!(
+            ParseError::InvalidCell {
+                row: 1,
+                col: 2,
+                found: 'o'
+            }
+            .to_string(),
+            "Invalid cell 'o' at row 1, column 2"
+        );
+    }
+
+    #[test]
+    fn test_source() {
+        let err = ParseError::from(GFError::InvalidModulus(0));
+        assert_eq!(
+            err.source().map(|source| source.to_string()),
+            Some(GFError::InvalidModulus(0).to_string())
+        );
+        assert!(ParseError::Empty.source().is_none());
+    }
+}
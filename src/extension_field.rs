@@ -0,0 +1,260 @@
+use crate::field::FieldElement;
+use crate::finite_field::GFElement;
+use itertools::Itertools;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GFExtElement {
+    // An element of the extension field GF(p^k), stored as a polynomial over
+    // GF(p) of degree below k with the coefficient of x^0 first. Arithmetic is
+    // done modulo the monic polynomial irreducible of degree k, which has to be
+    // irreducible over GF(p) for the elements to form a field (not checked).
+    pub coefficients: Vec<GFElement>,
+    pub irreducible: Vec<GFElement>,
+}
+
+impl GFExtElement {
+    pub fn new(coefficients: &[i32], irreducible: &[i32], p: i32) -> Self {
+        // Create the element sum(coefficients[i] * x^i) of GF(p^k), with k the
+        // degree of irreducible (also given lowest coefficient first)
+        let irreducible = irreducible
+            .iter()
+            .map(|&value| GFElement::new(value, p))
+            .collect_vec();
+        assert!(
+            irreducible.len() >= 2 && irreducible.last().is_some_and(|x| x.value == 1),
+            "Irreducible polynomial should be monic of degree at least 1"
+        );
+        let coefficients = coefficients
+            .iter()
+            .map(|&value| GFElement::new(value, p))
+            .collect_vec();
+        GFExtElement::reduced(coefficients, irreducible)
+    }
+
+    fn reduced(mut coefficients: Vec<GFElement>, irreducible: Vec<GFElement>) -> Self {
+        // Reduce a polynomial modulo irreducible, padding it to k coefficients
+        let degree = irreducible.len() - 1;
+        let p = irreducible[0].modulus;
+        while coefficients.len() > degree {
+            // Cancel the leading term by subtracting a multiple of irreducible
+            let lead = coefficients
+                .pop()
+                .expect("Polynomial is longer than degree");
+            let shift = coefficients.len() - degree;
+            for (idx, element) in irreducible.iter().take(degree).enumerate() {
+                coefficients[shift + idx] = coefficients[shift + idx] - lead * *element;
+            }
+        }
+        coefficients.resize(degree, GFElement::new(0, p));
+        GFExtElement {
+            coefficients,
+            irreducible,
+        }
+    }
+
+    pub fn characteristic(&self) -> i32 {
+        // The prime p of GF(p^k)
+        self.irreducible[0].modulus
+    }
+
+    pub fn pow(&self, exp: u64) -> GFExtElement {
+        // Raise the element to the power exp using square and multiply
+        let mut one = vec![0; self.coefficients.len()];
+        one[0] = 1;
+        let irreducible = self.irreducible.iter().map(|x| x.value).collect_vec();
+        let mut result = GFExtElement::new(&one, &irreducible, self.characteristic());
+        let mut base = self.clone();
+        let mut exp = exp;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base.clone();
+            }
+            base = base.clone() * base;
+            exp >>= 1;
+        }
+        result
+    }
+
+    pub fn frobenius(&self) -> GFExtElement {
+        // The Frobenius automorphism x -> x^p. It respects addition and
+        // multiplication and fixes exactly the elements of the base field GF(p).
+        self.pow(self.characteristic() as u64)
+    }
+
+    pub fn inverse(&self) -> Option<GFExtElement> {
+        // The multiplicative group of GF(q) has order q - 1, so x^(q - 2) is the
+        // inverse of every x != 0
+        if self.is_zero() {
+            return None;
+        }
+        let order = (self.characteristic() as u64).pow(self.coefficients.len() as u32);
+        Some(self.pow(order - 2))
+    }
+}
+
+impl FieldElement for GFExtElement {
+    fn zero_like(&self) -> Self {
+        GFExtElement {
+            coefficients: vec![GFElement::new(0, self.characteristic()); self.coefficients.len()],
+            irreducible: self.irreducible.clone(),
+        }
+    }
+
+    fn one_like(&self) -> Self {
+        let mut one = self.zero_like();
+        one.coefficients[0] = GFElement::new(1, self.characteristic());
+        one
+    }
+
+    fn is_zero(&self) -> bool {
+        self.coefficients.iter().all(|x| x.value == 0)
+    }
+}
+
+impl Add for GFExtElement {
+    type Output = GFExtElement;
+
+    fn add(self, other: GFExtElement) -> GFExtElement {
+        assert_eq!(self.irreducible, other.irreducible);
+        let coefficients = self
+            .coefficients
+            .iter()
+            .zip(other.coefficients.iter())
+            .map(|(a, b)| *a + *b)
+            .collect_vec();
+        GFExtElement {
+            coefficients,
+            irreducible: self.irreducible,
+        }
+    }
+}
+
+impl Sub for GFExtElement {
+    type Output = GFExtElement;
+
+    fn sub(self, other: GFExtElement) -> GFExtElement {
+        self + -other
+    }
+}
+
+impl Neg for GFExtElement {
+    type Output = GFExtElement;
+
+    fn neg(self) -> GFExtElement {
+        let coefficients = self.coefficients.iter().map(|x| -*x).collect_vec();
+        GFExtElement {
+            coefficients,
+            irreducible: self.irreducible,
+        }
+    }
+}
+
+impl Div for GFExtElement {
+    type Output = GFExtElement;
+
+    fn div(self, other: GFExtElement) -> GFExtElement {
+        // Dividing is multiplying by the inverse
+        let inverse = other.inverse().expect("Division by 0 attempted");
+        Mul::mul(self, inverse)
+    }
+}
+
+impl Mul for GFExtElement {
+    type Output = GFExtElement;
+
+    fn mul(self, other: GFExtElement) -> GFExtElement {
+        assert_eq!(self.irreducible, other.irreducible);
+        let p = self.characteristic();
+        let mut product =
+            vec![GFElement::new(0, p); self.coefficients.len() + other.coefficients.len() - 1];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                product[i + j] = product[i + j] + *a * *b;
+            }
+        }
+        GFExtElement::reduced(product, self.irreducible)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GFExtElement;
+    use crate::linalg::Matrix;
+    use itertools::Itertools;
+
+    fn field_elements(p: i32, irreducible: &[i32]) -> Vec<GFExtElement> {
+        // All p^k elements of the field
+        let degree = irreducible.len() - 1;
+        (0..degree)
+            .map(|_| 0..p)
+            .multi_cartesian_product()
+            .map(|coefficients| GFExtElement::new(&coefficients, irreducible, p))
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_mul_gf4() {
+        // GF(4) = GF(2)[x] / (x^2 + x + 1), so x * x = x + 1
+        let x = GFExtElement::new(&[0, 1], &[1, 1, 1], 2);
+        assert_eq!(x.clone() * x, GFExtElement::new(&[1, 1], &[1, 1, 1], 2));
+    }
+
+    #[test]
+    fn test_frobenius_gf4() {
+        let gf4 = |coefficients: &[i32]| GFExtElement::new(coefficients, &[1, 1, 1], 2);
+        assert_eq!(gf4(&[0, 0]).frobenius(), gf4(&[0, 0]));
+        assert_eq!(gf4(&[1, 0]).frobenius(), gf4(&[1, 0]));
+        assert_eq!(gf4(&[0, 1]).frobenius(), gf4(&[1, 1]));
+        assert_eq!(gf4(&[1, 1]).frobenius(), gf4(&[0, 1]));
+    }
+
+    #[test]
+    fn test_frobenius_is_automorphism() {
+        // GF(9) = GF(3)[x] / (x^2 + 1)
+        let elements = field_elements(3, &[1, 0, 1]);
+        for (a, b) in elements.iter().cartesian_product(elements.iter()) {
+            assert_eq!(
+                (a.clone() + b.clone()).frobenius(),
+                a.frobenius() + b.frobenius()
+            );
+            assert_eq!(
+                (a.clone() * b.clone()).frobenius(),
+                a.frobenius() * b.frobenius()
+            );
+        }
+        // Exactly the base field is fixed
+        let fixed = elements
+            .iter()
+            .filter(|element| element.frobenius() == **element)
+            .collect_vec();
+        assert_eq!(fixed.len(), 3);
+        assert!(
+            fixed
+                .iter()
+                .all(|element| element.coefficients[1].value == 0)
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        let elements = field_elements(3, &[1, 0, 1]);
+        assert_eq!(elements[0].inverse(), None);
+        for element in elements.iter().skip(1) {
+            let one = GFExtElement::new(&[1], &[1, 0, 1], 3);
+            assert_eq!(element.clone() * element.inverse().unwrap(), one);
+        }
+    }
+
+    #[test]
+    fn test_solution_over_gf4() {
+        // x + a y = 0 and a x + y = 1 over GF(4) with a^2 = a + 1: x = a y gives
+        // (a^2 + 1) y = a y = 1, so y = a^-1 = a + 1 and x = 1
+        let gf4 = |coefficients: &[i32]| GFExtElement::new(coefficients, &[1, 1, 1], 2);
+        let matrix = Matrix::new(vec![
+            vec![gf4(&[1]), gf4(&[0, 1]), gf4(&[0])],
+            vec![gf4(&[0, 1]), gf4(&[1]), gf4(&[1])],
+        ]);
+        assert_eq!(matrix.solution(), Some(vec![gf4(&[1]), gf4(&[1, 1])]));
+    }
+}
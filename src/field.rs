@@ -0,0 +1,25 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+pub trait FieldElement:
+    Clone
+    + PartialEq
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    // An element of a field, as needed by the generic parts of Matrix such as
+    // solution. Elements of GF(p) and GF(p^k) only know their field through the
+    // element itself, so the identities are taken from an existing element.
+
+    // The additive identity of the field self belongs to
+    fn zero_like(&self) -> Self;
+
+    // The multiplicative identity of the field self belongs to
+    fn one_like(&self) -> Self;
+
+    fn is_zero(&self) -> bool {
+        *self == self.zero_like()
+    }
+}
@@ -1,6 +1,8 @@
+use crate::error::{GFError, ParseError};
+use crate::field::FieldElement;
 use std::fmt;
 
-use std::ops::{Add, Div, Mul, Sub};
+use std::ops::{Add, Div, Mul, Neg, Sub};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct GFElement {
@@ -13,18 +15,118 @@ impl GFElement {
     pub fn new(v: i32, m: i32) -> Self {
         // Create a new GFElement
         // v: value
-        // m: modulus >= 0
-        assert!(m >= 0);
-        GFElement {
+        // m: modulus >= 2, a smaller modulus cannot define a field (0 would divide
+        //    by zero and 1 collapses every value to 0). Use try_new to get an error
+        //    instead of a panic.
+        match GFElement::try_new(v, m) {
+            Ok(element) => element,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn checked_from(v: i32, m: i32) -> Result<Self, GFError> {
+        // Create a new GFElement from a value that is already reduced, i.e. in
+        // [0, m). Unlike new, a value outside this range is an error instead of
+        // being reduced.
+        let element = GFElement::try_new(v, m)?;
+        if element.value != v {
+            return Err(GFError::OutOfRange {
+                value: v,
+                modulus: m,
+            });
+        }
+        Ok(element)
+    }
+
+    pub fn neg_one(m: i32) -> Self {
+        // The element -1, i.e. m - 1, of GF(m)
+        GFElement::new(-1, m)
+    }
+
+    pub fn parse(s: &str, modulus: i32) -> Result<Self, ParseError> {
+        // Parse a signed integer, surrounding whitespace is ignored, and reduce it
+        // into GF(modulus)
+        let value = s
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| ParseError::InvalidElement(s.to_string()))?;
+        Ok(GFElement::try_new(value, modulus)?)
+    }
+
+    pub fn to_string_with_modulus(&self) -> String {
+        // Format the element together with its field, e.g. "3 (mod 7)"
+        format!("{} (mod {})", self.value, self.modulus)
+    }
+
+    pub fn value_signed(&self) -> i32 {
+        // The balanced representative of the element in (-m/2, m/2], e.g. 5 over
+        // GF(7) is -2
+        if self.value > self.modulus / 2 {
+            self.value - self.modulus
+        } else {
+            self.value
+        }
+    }
+
+    pub fn same_value(&self, other: &GFElement) -> bool {
+        // Compare only the canonical values, e.g. 0 over GF(2) and 0 over GF(3)
+        // are the same value. == stays strict and also compares the moduli, as
+        // elements of different fields are never equal.
+        self.value == other.value
+    }
+
+    pub fn try_new(v: i32, m: i32) -> Result<Self, GFError> {
+        // Create a new GFElement, returning an error instead of panicking for a
+        // modulus that cannot define a field (m < 2)
+        if m < 2 {
+            return Err(GFError::InvalidModulus(m));
+        }
+        Ok(GFElement {
             value: v.rem_euclid(m),
             modulus: m,
-        }
+        })
+    }
+}
+
+impl Default for GFElement {
+    fn default() -> Self {
+        // Zero of GF(2), the field of the classic on/off puzzle. An element does
+        // not know which field it is wanted in, so replace the default with
+        // GFElement::new(0, modulus) for any other modulus.
+        GFElement::new(0, 2)
+    }
+}
+
+impl FieldElement for GFElement {
+    fn zero_like(&self) -> Self {
+        GFElement::new(0, self.modulus)
+    }
+
+    fn one_like(&self) -> Self {
+        GFElement::new(1, self.modulus)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.value == 0
     }
 }
 
 impl fmt::Display for GFElement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.value)
+        // The alternate flag ({:#}) prints values above modulus / 2 as their
+        // negative representative, e.g. 6 over GF(7) as -1
+        if f.alternate() {
+            write!(f, "{}", self.value_signed())
+        } else {
+            write!(f, "{}", self.value)
+        }
+    }
+}
+
+impl PartialEq<i32> for GFElement {
+    fn eq(&self, other: &i32) -> bool {
+        // Compare against an integer, which is reduced into the field first
+        self.value == other.rem_euclid(self.modulus)
     }
 }
 
@@ -33,10 +135,7 @@ impl Add for GFElement {
 
     fn add(self, other: GFElement) -> Self {
         assert!(self.modulus == other.modulus);
-        GFElement::new(
-            (self.value + other.value).rem_euclid(self.modulus),
-            self.modulus,
-        )
+        GFElement::new(self.value + other.value, self.modulus)
     }
 }
 
@@ -45,10 +144,32 @@ impl Sub for GFElement {
 
     fn sub(self, other: GFElement) -> Self {
         assert!(self.modulus == other.modulus);
-        GFElement::new(
-            (self.value - other.value).rem_euclid(self.modulus),
-            self.modulus,
-        )
+        GFElement::new(self.value - other.value, self.modulus)
+    }
+}
+
+impl Add<i32> for GFElement {
+    type Output = GFElement;
+
+    fn add(self, other: i32) -> Self {
+        // The integer is taken as an element of the same field
+        self + GFElement::new(other, self.modulus)
+    }
+}
+
+impl Sub<i32> for GFElement {
+    type Output = GFElement;
+
+    fn sub(self, other: i32) -> Self {
+        self - GFElement::new(other, self.modulus)
+    }
+}
+
+impl Neg for GFElement {
+    type Output = GFElement;
+
+    fn neg(self) -> Self {
+        GFElement::new(-self.value, self.modulus)
     }
 }
 
@@ -57,10 +178,7 @@ impl Mul for GFElement {
 
     fn mul(self, other: GFElement) -> Self {
         assert_eq!(self.modulus, other.modulus);
-        GFElement::new(
-            (self.value * other.value).rem_euclid(self.modulus),
-            self.modulus,
-        )
+        GFElement::new(self.value * other.value, self.modulus)
     }
 }
 
@@ -80,26 +198,249 @@ impl Div for GFElement {
     }
 }
 
+// Reference arithmetic, so generic code does not need to rely on Copy
+impl Add<&GFElement> for &GFElement {
+    type Output = GFElement;
+
+    fn add(self, other: &GFElement) -> GFElement {
+        *self + *other
+    }
+}
+
+impl Sub<&GFElement> for &GFElement {
+    type Output = GFElement;
+
+    fn sub(self, other: &GFElement) -> GFElement {
+        *self - *other
+    }
+}
+
+impl Mul<&GFElement> for &GFElement {
+    type Output = GFElement;
+
+    fn mul(self, other: &GFElement) -> GFElement {
+        *self * *other
+    }
+}
+
+impl Div<&GFElement> for &GFElement {
+    type Output = GFElement;
+
+    fn div(self, other: &GFElement) -> GFElement {
+        *self / *other
+    }
+}
+
 // Find the multiplicative inverse, needed for Division
 impl GFElement {
+    pub fn inverse(&self) -> Option<GFElement> {
+        // The element x with self * x == 1, None if there is none (for 0, or
+        // elements sharing a factor with a composite modulus)
+        if gcd(self.value, self.modulus) != 1 {
+            return None;
+        }
+        (0..self.modulus)
+            .find(|i| (self.value * i).rem_euclid(self.modulus) == 1)
+            .map(|i| GFElement::new(i, self.modulus))
+    }
+
+    pub fn try_div(&self, other: GFElement) -> Result<GFElement, GFError> {
+        // Divide like the Div operator, but return an error instead of panicking
+        // for mixed moduli, a zero divisor or a divisor without an inverse (which
+        // only happens for composite moduli)
+        if self.modulus != other.modulus {
+            return Err(GFError::ModulusMismatch {
+                expected: self.modulus,
+                found: other.modulus,
+            });
+        }
+        if other.value == 0 {
+            return Err(GFError::DivisionByZero);
+        }
+        let inverse = other.inverse().ok_or(GFError::NotInvertible {
+            value: other.value,
+            modulus: other.modulus,
+        })?;
+        Ok(*self * inverse)
+    }
+
+    pub fn batch_inverse(elements: &[GFElement]) -> Vec<Option<GFElement>> {
+        // Invert all elements at once using Montgomery's trick: the product of
+        // all non-zero elements is inverted once, and the inverse of each element
+        // is recovered from it and the prefix products by walking backwards.
+        // Zero elements have no inverse. If the product is not invertible (only
+        // possible for composite moduli) every element is inverted separately.
+        let nonzero = elements
+            .iter()
+            .enumerate()
+            .filter(|(_, element)| element.value != 0)
+            .collect::<Vec<_>>();
+        let mut inverses = vec![None; elements.len()];
+        let Some((_, first)) = nonzero.first() else {
+            return inverses;
+        };
+
+        let mut prefix_products = Vec::with_capacity(nonzero.len());
+        let mut product = GFElement::new(1, first.modulus);
+        for (_, element) in nonzero.iter() {
+            product = product * **element;
+            prefix_products.push(product);
+        }
+        let Some(mut inverse) = product.inverse() else {
+            return elements.iter().map(|element| element.inverse()).collect();
+        };
+
+        for (k, (idx, element)) in nonzero.iter().enumerate().rev() {
+            // inverse holds the inverse of the product of the first k + 1 elements
+            inverses[*idx] = Some(match k {
+                0 => inverse,
+                _ => inverse * prefix_products[k - 1],
+            });
+            inverse = inverse * **element;
+        }
+        inverses
+    }
+
     fn mult_inverse(&self) -> GFElement {
-        for i in 0..self.modulus {
-            if (self.value * i).rem_euclid(self.modulus) == 1 {
-                return GFElement::new(i, self.modulus);
+        self.inverse().unwrap_or_else(|| {
+            panic!(
+                "Multiplicative inverse for value {} not found for base {}",
+                self.value, self.modulus
+            )
+        })
+    }
+}
+
+impl GFElement {
+    pub fn pow(&self, exp: u32) -> GFElement {
+        self.pow_u64(exp as u64)
+    }
+
+    fn pow_u64(&self, exp: u64) -> GFElement {
+        // Raise the element to the power exp using square and multiply, wide
+        // enough for the magnitude of any i64 exponent
+        let mut result = GFElement::new(1, self.modulus);
+        let mut base = *self;
+        let mut exp = exp;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base;
             }
+            base = base * base;
+            exp >>= 1;
         }
-        let msg = format!(
-            "Multiplicative inverse for value {} not found for base {}",
-            self.value, self.modulus
-        );
-        panic!("{}", msg);
+        result
+    }
+
+    pub fn order(&self) -> Option<u32> {
+        // The multiplicative order: the smallest k > 0 with self^k == 1.
+        // Zero (and, for composite moduli, any non-invertible element) has none.
+        (1..self.modulus as u32).find(|&k| self.pow(k).value == 1)
+    }
+
+    pub fn power_table(&self) -> Vec<GFElement> {
+        // The powers self^0, self^1, ..., self^(order - 1), after which they
+        // repeat, so self^k is table[k % order]. Empty for elements without a
+        // multiplicative order such as zero.
+        let Some(order) = self.order() else {
+            return vec![];
+        };
+        std::iter::successors(Some(GFElement::new(1, self.modulus)), |power| {
+            Some(*power * *self)
+        })
+        .take(order as usize)
+        .collect()
+    }
+
+    pub fn pow_signed(&self, exp: i64) -> Option<GFElement> {
+        // Raise the element to a possibly negative power, a negative exponent
+        // raises the inverse instead. None if the inverse is needed but does not
+        // exist.
+        if exp >= 0 {
+            return Some(self.pow_u64(exp as u64));
+        }
+        self.inverse()
+            .map(|inverse| inverse.pow_u64(exp.unsigned_abs()))
     }
 }
 
+#[cfg(feature = "rand")]
+impl GFElement {
+    pub fn random(modulus: i32, rng: &mut impl rand::Rng) -> GFElement {
+        // Draw a uniformly distributed element of GF(modulus)
+        use rand::RngExt;
+        GFElement::new(rng.random_range(0..modulus), modulus)
+    }
+}
+
+fn is_prime(n: i32) -> bool {
+    // Trial division, sufficient for the moduli used by puzzles
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0)
+}
+
+pub fn gcd(a: i32, b: i32) -> i32 {
+    // Greatest common divisor by the Euclidean algorithm, the signs of the
+    // inputs are ignored and gcd(0, 0) is 0
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+pub fn lcm(a: i32, b: i32) -> i32 {
+    // Least common multiple, the signs of the inputs are ignored and the
+    // result is 0 if either input is 0
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    (a / gcd(a, b) * b).abs()
+}
+
+pub fn pack_gf2(solution: &[GFElement]) -> Vec<u8> {
+    // Pack a GF(2) vector into bytes, element i is stored in bit i % 8 of byte
+    // i / 8. Unused bits of the last byte are 0.
+    assert!(
+        solution.iter().all(|element| element.modulus == 2),
+        "Only GF(2) elements can be packed"
+    );
+    let mut bytes = vec![0u8; solution.len().div_ceil(8)];
+    for (idx, element) in solution.iter().enumerate() {
+        bytes[idx / 8] |= (element.value as u8) << (idx % 8);
+    }
+    bytes
+}
+
+pub fn unpack_gf2(bytes: &[u8], len: usize) -> Vec<GFElement> {
+    // Unpack the first len elements of a GF(2) vector packed by pack_gf2
+    assert!(
+        bytes.len() * 8 >= len,
+        "Not enough bytes for {} elements",
+        len
+    );
+    (0..len)
+        .map(|idx| GFElement::new(((bytes[idx / 8] >> (idx % 8)) & 1) as i32, 2))
+        .collect()
+}
+
+pub fn primitive_root(modulus: i32) -> Option<GFElement> {
+    // Find a generator of the multiplicative group of GF(modulus), that is an
+    // element of order modulus - 1. Only prime moduli define a field.
+    if !is_prime(modulus) {
+        return None;
+    }
+    (1..modulus)
+        .map(|value| GFElement::new(value, modulus))
+        .find(|element| element.order() == Some((modulus - 1) as u32))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::GFElement;
+    use super::{GFElement, gcd, lcm, pack_gf2, primitive_root, unpack_gf2};
+    use crate::error::{GFError, ParseError};
+    use itertools::Itertools;
     use rstest::rstest;
+    use std::ops::{Add, Div, Mul, Sub};
 
     #[rstest]
     #[case::zero_plus_zero(0, 0, 0)]
@@ -125,6 +466,49 @@ mod tests {
         assert_eq!(a - b, c);
     }
 
+    #[rstest]
+    #[case(2)]
+    #[case(3)]
+    #[case(7)]
+    #[case(46337)]
+    fn test_operators_at_boundaries(#[case] modulus: i32) {
+        // Sums, differences and products of the extreme values are reduced once
+        // by new, compare against a reduction of the exact result
+        let exact = |value: i64| GFElement::new(value.rem_euclid(modulus as i64) as i32, modulus);
+        let values = [0, 1, modulus - 1];
+        for (&a, &b) in values.iter().cartesian_product(values.iter()) {
+            let (x, y) = (GFElement::new(a, modulus), GFElement::new(b, modulus));
+            let (a, b) = (a as i64, b as i64);
+            assert_eq!(x + y, exact(a + b));
+            assert_eq!(x - y, exact(a - b));
+            assert_eq!(x * y, exact(a * b));
+        }
+    }
+
+    #[rstest]
+    #[case(2, 4, 1)]
+    #[case(2, -3, 4)]
+    #[case(0, 12, 2)]
+    fn test_add_integer(#[case] a: i32, #[case] b: i32, #[case] expected: i32) {
+        assert_eq!(GFElement::new(a, 5) + b, GFElement::new(expected, 5));
+    }
+
+    #[rstest]
+    #[case(2, 4, 3)]
+    #[case(2, -3, 0)]
+    #[case(0, 1, 4)]
+    fn test_sub_integer(#[case] a: i32, #[case] b: i32, #[case] expected: i32) {
+        assert_eq!(GFElement::new(a, 5) - b, GFElement::new(expected, 5));
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(1, 4)]
+    #[case(3, 2)]
+    fn test_neg(#[case] a: i32, #[case] expected: i32) {
+        assert_eq!(-GFElement::new(a, 5), GFElement::new(expected, 5));
+    }
+
     #[rstest]
     #[case(1, 1, 1)]
     #[case(2, 0, 0)]
@@ -157,10 +541,344 @@ mod tests {
         assert_eq!(dividend / divisor, expected);
     }
 
+    fn apply_by_ref<T>(a: &T, b: &T) -> [T; 4]
+    where
+        for<'a> &'a T: Add<&'a T, Output = T>
+            + Sub<&'a T, Output = T>
+            + Mul<&'a T, Output = T>
+            + Div<&'a T, Output = T>,
+    {
+        // Generic code that only has references to the elements
+        [a + b, a - b, a * b, a / b]
+    }
+
+    #[test]
+    fn test_reference_operators() {
+        let a = GFElement::new(5, 7);
+        let b = GFElement::new(3, 7);
+        assert_eq!(
+            apply_by_ref(&a, &b),
+            [1, 2, 1, 4].map(|value| GFElement::new(value, 7))
+        );
+        assert_eq!(apply_by_ref(&a, &b), [a + b, a - b, a * b, a / b]);
+    }
+
+    #[test]
+    fn test_default() {
+        assert_eq!(GFElement::default(), GFElement::new(0, 2));
+    }
+
     #[test]
     fn test_display() {
         let a = GFElement::new(2, 3);
         let expected = "2";
         assert_eq!(format!("{a}"), expected);
     }
+
+    #[test]
+    fn test_to_string_with_modulus() {
+        let element = GFElement::new(10, 7);
+        assert_eq!(element.to_string_with_modulus(), "3 (mod 7)");
+        assert_eq!(element.to_string(), "3");
+    }
+
+    #[rstest]
+    #[case(6, 7, "6", "-1")]
+    #[case(4, 7, "4", "-3")]
+    #[case(3, 7, "3", "3")]
+    #[case(0, 7, "0", "0")]
+    #[case(1, 2, "1", "1")]
+    fn test_display_alternate(
+        #[case] value: i32,
+        #[case] modulus: i32,
+        #[case] plain: &str,
+        #[case] alternate: &str,
+    ) {
+        let element = GFElement::new(value, modulus);
+        assert_eq!(format!("{}", element), plain);
+        assert_eq!(format!("{:#}", element), alternate);
+    }
+
+    #[rstest]
+    #[case("3", Ok(GFElement::new(3, 5)))]
+    #[case("-1", Ok(GFElement::new(4, 5)))]
+    #[case(" 12 ", Ok(GFElement::new(2, 5)))]
+    #[case("abc", Err(ParseError::InvalidElement("abc".to_string())))]
+    #[case("", Err(ParseError::InvalidElement("".to_string())))]
+    fn test_parse(#[case] s: &str, #[case] expected: Result<GFElement, ParseError>) {
+        assert_eq!(GFElement::parse(s, 5), expected);
+    }
+
+    #[test]
+    fn test_parse_invalid_modulus() {
+        assert_eq!(
+            GFElement::parse("1", 1),
+            Err(ParseError::InvalidModulus(GFError::InvalidModulus(1)))
+        );
+    }
+
+    #[rstest]
+    #[case(5, 7, -2)]
+    #[case(2, 7, 2)]
+    #[case(0, 7, 0)]
+    #[case(3, 7, 3)]
+    #[case(4, 7, -3)]
+    #[case(1, 2, 1)]
+    #[case(2, 4, 2)]
+    fn test_value_signed(#[case] value: i32, #[case] modulus: i32, #[case] expected: i32) {
+        assert_eq!(GFElement::new(value, modulus).value_signed(), expected);
+    }
+
+    #[test]
+    fn test_eq_integer() {
+        assert_eq!(GFElement::new(3, 2), 1);
+        assert_eq!(GFElement::new(2, 3), -1);
+        assert_eq!(GFElement::new(2, 3), 5);
+        assert_ne!(GFElement::new(2, 3), 1);
+    }
+
+    #[rstest]
+    #[case(GFElement::new(0, 2), GFElement::new(0, 3), true, false)]
+    #[case(GFElement::new(1, 2), GFElement::new(4, 3), true, false)]
+    #[case(GFElement::new(1, 2), GFElement::new(2, 3), false, false)]
+    #[case(GFElement::new(4, 5), GFElement::new(-1, 5), true, true)]
+    fn test_same_value(
+        #[case] a: GFElement,
+        #[case] b: GFElement,
+        #[case] same_value: bool,
+        #[case] equal: bool,
+    ) {
+        assert_eq!(a.same_value(&b), same_value);
+        assert_eq!(b.same_value(&a), same_value);
+        assert_eq!(a == b, equal);
+    }
+
+    #[test]
+    fn test_neg_one() {
+        assert_eq!(GFElement::neg_one(7), GFElement::new(6, 7));
+        assert_eq!(
+            GFElement::neg_one(7) + GFElement::new(1, 7),
+            GFElement::new(0, 7)
+        );
+        assert_eq!(format!("{:#}", GFElement::neg_one(13)), "-1");
+    }
+
+    #[rstest]
+    #[case(3, 0, 1)]
+    #[case(3, 1, 3)]
+    #[case(3, 2, 2)]
+    #[case(3, 6, 1)]
+    #[case(2, 10, 2)]
+    fn test_pow(#[case] base: i32, #[case] exp: u32, #[case] expected: i32) {
+        assert_eq!(
+            GFElement::new(base, 7).pow(exp),
+            GFElement::new(expected, 7)
+        );
+    }
+
+    #[rstest]
+    #[case::primitive_root(3, Some(6))]
+    #[case(2, Some(3))]
+    #[case(6, Some(2))]
+    #[case(1, Some(1))]
+    #[case::zero(0, None)]
+    fn test_order(#[case] value: i32, #[case] expected: Option<u32>) {
+        assert_eq!(GFElement::new(value, 7).order(), expected);
+    }
+
+    #[rstest]
+    #[case(2)]
+    #[case(7)]
+    #[case(13)]
+    fn test_primitive_root(#[case] modulus: i32) {
+        let root = primitive_root(modulus).expect("Prime modulus has a primitive root");
+        assert_eq!(root.order(), Some((modulus - 1) as u32));
+    }
+
+    #[test]
+    fn test_power_table() {
+        // 3 generates GF(7): 1, 3, 2, 6, 4, 5
+        let root = primitive_root(7).unwrap();
+        let table = root.power_table();
+        assert_eq!(table.len(), 6);
+        assert_eq!(
+            table.iter().map(|x| x.value).collect::<Vec<_>>(),
+            vec![1, 3, 2, 6, 4, 5]
+        );
+        for exp in 0..20 {
+            assert_eq!(table[exp as usize % 6], root.pow(exp));
+        }
+        assert_eq!(GFElement::new(2, 7).power_table().len(), 3);
+        assert!(GFElement::new(0, 7).power_table().is_empty());
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(4)]
+    #[case(9)]
+    fn test_primitive_root_non_prime(#[case] modulus: i32) {
+        assert_eq!(primitive_root(modulus), None);
+    }
+
+    #[rstest]
+    #[case(12, 18, 6)]
+    #[case(-12, 18, 6)]
+    #[case(12, -18, 6)]
+    #[case(7, 13, 1)]
+    #[case(0, 5, 5)]
+    #[case(0, 0, 0)]
+    fn test_gcd(#[case] a: i32, #[case] b: i32, #[case] expected: i32) {
+        assert_eq!(gcd(a, b), expected);
+    }
+
+    #[rstest]
+    #[case(4, 6, 12)]
+    #[case(-4, 6, 12)]
+    #[case(-4, -6, 12)]
+    #[case(3, 5, 15)]
+    #[case(0, 5, 0)]
+    fn test_lcm(#[case] a: i32, #[case] b: i32, #[case] expected: i32) {
+        assert_eq!(lcm(a, b), expected);
+    }
+
+    #[test]
+    fn test_pack_gf2_round_trip() {
+        let solution =
+            [1, 0, 1, 1, 0, 0, 0, 1, 1, 0, 0, 1, 1].map(|value| GFElement::new(value, 2));
+        let bytes = pack_gf2(&solution);
+        assert_eq!(bytes, vec![0b1000_1101, 0b0001_1001]);
+        assert_eq!(unpack_gf2(&bytes, solution.len()), solution);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only GF(2)")]
+    fn test_pack_gf2_wrong_modulus() {
+        pack_gf2(&[GFElement::new(1, 3)]);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(1)]
+    #[case(-3)]
+    fn test_try_new_invalid_modulus(#[case] modulus: i32) {
+        assert_eq!(
+            GFElement::try_new(1, modulus),
+            Err(GFError::InvalidModulus(modulus))
+        );
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(1)]
+    #[should_panic(expected = "expected at least 2")]
+    fn test_new_invalid_modulus(#[case] modulus: i32) {
+        GFElement::new(1, modulus);
+    }
+
+    #[rstest]
+    #[case::zero(0, Ok(GFElement::new(0, 5)))]
+    #[case::in_range(4, Ok(GFElement::new(4, 5)))]
+    #[case::negative(-1, Err(GFError::OutOfRange { value: -1, modulus: 5 }))]
+    #[case::over_range(5, Err(GFError::OutOfRange { value: 5, modulus: 5 }))]
+    fn test_checked_from(#[case] value: i32, #[case] expected: Result<GFElement, GFError>) {
+        assert_eq!(GFElement::checked_from(value, 5), expected);
+    }
+
+    #[test]
+    fn test_try_new() {
+        assert_eq!(GFElement::try_new(-1, 5), Ok(GFElement::new(4, 5)));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_random_covers_all_residues() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut counts = [0; 5];
+        for _ in 0..1000 {
+            let element = GFElement::random(5, &mut rng);
+            assert_eq!(element.modulus, 5);
+            counts[element.value as usize] += 1;
+        }
+        // Every residue shows up, roughly 200 times each
+        assert!(counts.iter().all(|&count| count > 150), "{:?}", counts);
+    }
+
+    #[rstest]
+    #[case(2, 7, Some(4))]
+    #[case(3, 7, Some(5))]
+    #[case(1, 2, Some(1))]
+    #[case(0, 7, None)]
+    #[case::composite(2, 4, None)]
+    fn test_inverse(#[case] value: i32, #[case] modulus: i32, #[case] expected: Option<i32>) {
+        assert_eq!(
+            GFElement::new(value, modulus).inverse(),
+            expected.map(|inverse| GFElement::new(inverse, modulus))
+        );
+    }
+
+    #[rstest]
+    #[case::success(
+        GFElement::new(5, 13),
+        GFElement::new(9, 13),
+        Ok(GFElement::new(2, 13))
+    )]
+    #[case::division_by_zero(
+        GFElement::new(1, 7),
+        GFElement::new(0, 7),
+        Err(GFError::DivisionByZero)
+    )]
+    #[case::modulus_mismatch(
+        GFElement::new(1, 7),
+        GFElement::new(1, 5),
+        Err(GFError::ModulusMismatch { expected: 7, found: 5 })
+    )]
+    #[case::not_invertible(
+        GFElement::new(1, 4),
+        GFElement::new(2, 4),
+        Err(GFError::NotInvertible { value: 2, modulus: 4 })
+    )]
+    fn test_try_div(
+        #[case] dividend: GFElement,
+        #[case] divisor: GFElement,
+        #[case] expected: Result<GFElement, GFError>,
+    ) {
+        assert_eq!(dividend.try_div(divisor), expected);
+    }
+
+    #[test]
+    fn test_batch_inverse() {
+        let elements = [3, 0, 7, 12, 1, 0, 5].map(|value| GFElement::new(value, 13));
+        let expected = elements
+            .iter()
+            .map(|element| element.inverse())
+            .collect::<Vec<_>>();
+        assert_eq!(GFElement::batch_inverse(&elements), expected);
+        assert_eq!(GFElement::batch_inverse(&[]), vec![]);
+    }
+
+    #[test]
+    fn test_batch_inverse_composite() {
+        let elements = [1, 2, 3].map(|value| GFElement::new(value, 4));
+        assert_eq!(
+            GFElement::batch_inverse(&elements),
+            vec![Some(GFElement::new(1, 4)), None, Some(GFElement::new(3, 4))]
+        );
+    }
+
+    #[rstest]
+    #[case::inverse(2, -1, Some(4))]
+    #[case::inverse_squared(2, -2, Some(2))]
+    #[case::positive(2, 3, Some(1))]
+    #[case::zero_exponent(0, 0, Some(1))]
+    #[case::zero_positive(0, 2, Some(0))]
+    #[case::zero_negative(0, -1, None)]
+    fn test_pow_signed(#[case] base: i32, #[case] exp: i64, #[case] expected: Option<i32>) {
+        assert_eq!(
+            GFElement::new(base, 7).pow_signed(exp),
+            expected.map(|value| GFElement::new(value, 7))
+        );
+    }
 }
@@ -1,5 +1,10 @@
-mod finite_field;
-mod linalg;
+pub mod error;
+pub mod extension_field;
+pub mod field;
+pub mod finite_field;
+pub mod linalg;
+pub mod puzzle;
+pub mod sparse;
 
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
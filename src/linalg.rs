@@ -1,310 +1,1638 @@
+use crate::error::{LinAlgError, ParseError};
+use crate::field::FieldElement;
 use crate::finite_field::GFElement;
-use itertools::Itertools;
+use itertools::{Either, Itertools};
 use std::fmt::Display;
+use std::io::BufRead;
+use std::iter;
+use std::ops::Range;
 
-pub struct Matrix {
-    rows: Vec<Vec<GFElement>>,
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Matrix<F = GFElement> {
+    pub(crate) rows: Vec<Vec<F>>,
 }
 
-impl Matrix {
-    pub fn new(rows: Vec<Vec<GFElement>>) -> Self {
+impl<F: FieldElement> Matrix<F> {
+    // The parts of Matrix that only need field arithmetic, shared by matrices
+    // over GF(p), GF(p^k) or any other FieldElement
+
+    pub fn new(rows: Vec<Vec<F>>) -> Self {
         Matrix { rows }
     }
 
-    pub fn to_rref(&self) -> Matrix {
-        // Convert the matrix to reduced row echelon form
+    pub fn n_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn n_cols(&self) -> usize {
+        // The column count is taken from the first row, an empty matrix has 0 columns
+        self.rows.first().map_or(0, |row| row.len())
+    }
+
+    pub fn solve_rref(&self) -> Matrix<F> {
+        // Reduce the coefficient part of the augmented matrix to RREF form,
+        // carrying the augmentation along without ever using it as a pivot
+        // column. Unlike to_rref, an inconsistent row keeps its target value
+        // and the other rows keep theirs.
+        let mut rref = self.clone();
+        let n_cols = rref.n_cols();
+        rref.reduce(n_cols.saturating_sub(1), |_| {}, |_, _| {});
+        rref
+    }
+
+    fn reduce(
+        &mut self,
+        n_pivot_cols: usize,
+        mut on_op: impl FnMut(RowOp<F>),
+        mut cb: impl FnMut(usize, usize),
+    ) {
+        // Row reduce the matrix, looking for pivots in the first n_pivot_cols
+        // columns only. on_op is told about every row operation performed and cb
+        // is called after each pivot step.
         let n_rows = self.rows.len();
-        let n_cols = self.rows[0].len();
-
-        let mut new_rows = self.rows.clone();
-
-        // Generate reduced row echelon form by walking through the columns
-        'next_row: for row_idx in 0..self.rows.len() {
-            // Verify that the first column starts with a non-zero number
-            for col_idx in 0..self.rows[0].len() {
-                // First, if the first column does not start with a non-zero number,
-                // try to find a row that does
-
-                match new_rows[row_idx][col_idx].value {
-                    // If zero, check if another column is not null and then swap
-                    0 => {
-                        for lower_row_idx in row_idx + 1..n_rows {
-                            if new_rows[lower_row_idx][col_idx].value != 0 {
-                                // A lower row has a non-zero element in the leading column,
-                                // swap, normalize and zero the rows below it
-                                new_rows.swap(row_idx, lower_row_idx);
-                            }
-                        }
-                    }
-                    // Do nothing if the first number is non-zero
-                    _ => {}
-                }
 
-                match new_rows[row_idx][col_idx].value {
-                    // This time, if it's 0, when know there is no other non-zero-starting column
-                    0 => {}
-                    // If non-zero value, use this value to reduce other rows
-                    _ => {
-                        // Scale the current row by its first element
-                        let scale = new_rows[row_idx][col_idx];
-                        for scale_col_idx in col_idx..n_cols {
-                            new_rows[row_idx][scale_col_idx] =
-                                new_rows[row_idx][scale_col_idx] / scale;
-                        }
-                        // zero all other columns
-                        for other_row_idx in 0..n_rows {
-                            if other_row_idx == row_idx {
-                                continue;
-                            }
-                            if new_rows[other_row_idx][col_idx].value != 0 {
-                                // A leading non-zero element exists, scale the current row
-                                // accordingly and subtract it from the lower row to zero leading value
-                                let scale = new_rows[other_row_idx][col_idx];
-                                for lower_col_idx in col_idx..n_cols {
-                                    new_rows[other_row_idx][lower_col_idx] = new_rows
-                                        [other_row_idx][lower_col_idx]
-                                        - scale * new_rows[row_idx][lower_col_idx];
-                                }
-                            }
-                        }
-                        continue 'next_row;
-                    }
+        let new_rows = &mut self.rows;
+
+        // Generate reduced row echelon form by walking through the columns, the next
+        // pivot found is placed in pivot_row_idx
+        let mut pivot_row_idx = 0;
+        for col_idx in 0..n_pivot_cols {
+            if pivot_row_idx == n_rows {
+                break;
+            }
+
+            // Find the first row at or below the pivot row with a non-zero number in
+            // this column. If there is none, the column has no pivot.
+            let Some(found_row_idx) =
+                (pivot_row_idx..n_rows).find(|&row_idx| !new_rows[row_idx][col_idx].is_zero())
+            else {
+                continue;
+            };
+            if found_row_idx != pivot_row_idx {
+                new_rows.swap(pivot_row_idx, found_row_idx);
+                on_op(RowOp::Swap(pivot_row_idx, found_row_idx));
+            }
+
+            // Scale the pivot row by its leading element
+            let scale = new_rows[pivot_row_idx][col_idx].clone();
+            let one = scale.one_like();
+            if scale != one {
+                on_op(RowOp::Scale(pivot_row_idx, one / scale.clone()));
+            }
+            for element in new_rows[pivot_row_idx].iter_mut().skip(col_idx) {
+                *element = element.clone() / scale.clone();
+            }
+
+            // Zero the column in all other rows
+            let pivot_row = new_rows[pivot_row_idx].clone();
+            for (other_row_idx, other_row) in new_rows.iter_mut().enumerate() {
+                if other_row_idx == pivot_row_idx || other_row[col_idx].is_zero() {
+                    continue;
+                }
+                // Scale the pivot row accordingly and subtract it to zero the value
+                let scale = other_row[col_idx].clone();
+                on_op(RowOp::AddScaled {
+                    target: other_row_idx,
+                    source: pivot_row_idx,
+                    factor: -scale.clone(),
+                });
+                for (element, pivot_element) in
+                    other_row.iter_mut().zip(pivot_row.iter()).skip(col_idx)
+                {
+                    *element = element.clone() - scale.clone() * pivot_element.clone();
                 }
             }
+
+            pivot_row_idx += 1;
+            cb(pivot_row_idx, n_rows);
         }
+    }
 
-        Matrix::new(new_rows)
+    pub fn is_rref(&self) -> bool {
+        // Check whether the matrix is in reduced row echelon form: every non-zero
+        // row starts with a pivot 1, pivots strictly advance to the right going
+        // down the rows, all other elements of a pivot column are 0 and the zero
+        // rows are at the bottom
+        let mut last_pivot_idx = None;
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            let Some(pivot_idx) = row.iter().position(|x| !x.is_zero()) else {
+                // A zero row may only be followed by zero rows
+                return self.rows[row_idx..]
+                    .iter()
+                    .all(|row| row.iter().all(|x| x.is_zero()));
+            };
+            let pivot = &row[pivot_idx];
+            if *pivot != pivot.one_like() || last_pivot_idx.is_some_and(|last| pivot_idx <= last) {
+                return false;
+            }
+            let column_is_clear = self
+                .rows
+                .iter()
+                .enumerate()
+                .all(|(other_row_idx, other_row)| {
+                    other_row_idx == row_idx || other_row[pivot_idx].is_zero()
+                });
+            if !column_is_clear {
+                return false;
+            }
+            last_pivot_idx = Some(pivot_idx);
+        }
+        true
     }
 
-    pub fn is_solvable(&self) -> bool {
-        // Determine whether the puzzle corresponding to the matrix is solvable.
-        // It will be assumed that the right most column is the target vector of
-        // the augmented matrix
+    fn is_any_row_unsolvable(&self) -> bool {
+        // Any row of the form (0,0,0,...,k) is unsolvable for k<>0.
+        // This would correspond to a non zero value being the result of a sum
+        // of values multiplied by 0.
+        self.rows.iter().any(|row| {
+            row.iter().take(row.len() - 1).all(|x| x.is_zero())
+                && row.last().is_some_and(|x| !x.is_zero())
+        })
+    }
 
-        let matrix_rref = self.to_rref();
+    pub fn last_column(&self) -> Vec<F> {
+        // The last column of the matrix, i.e. the target vector of an augmented
+        // matrix or the solution values once it is reduced
+        self.rows
+            .iter()
+            .map(|row| {
+                row.last()
+                    .expect("Matrix should have at least one column")
+                    .clone()
+            })
+            .collect_vec()
+    }
 
-        // First, check whether any row is unsolvable
+    pub fn solution(&self) -> Option<Vec<F>> {
+        // If the Puzzle is solvable, read the solution off the RREF form matrix.
+        // Every non-zero row fixes the variable of its pivot column to the value of
+        // the augmentation, variables without a pivot (free variables) are set to 0.
+        // A matrix already in RREF form is not reduced again.
+        let matrix_rref = if self.is_rref() {
+            self.clone()
+        } else {
+            self.solve_rref()
+        };
         if matrix_rref.is_any_row_unsolvable() {
-            return false;
+            return None;
         }
 
-        // If no row is unsolvable and every row has a pivot, the puzzle is
-        // solvable
-        matrix_rref.unaugmented_matrix().every_row_has_a_pivot()
-    }
-
-    fn unaugmented_matrix(&self) -> Self {
-        // Create a new matrix without the last column (the augmentation)
-        let row_len = self
+        let zero = self
             .rows
             .first()
-            .expect("Matrix should have at least one row")
-            .len();
-        let rows = self
-            .rows
-            .iter()
-            .map(|row| row.iter().take(row_len - 1).copied().collect_vec())
+            .and_then(|row| row.first())
+            .expect("Matrix should have at least one element")
+            .zero_like();
+        let mut solution = vec![zero; self.n_cols() - 1];
+        for (row, value) in matrix_rref.rows.iter().zip(matrix_rref.last_column()) {
+            if let Some(pivot_idx) = row.iter().take(row.len() - 1).position(|x| !x.is_zero()) {
+                solution[pivot_idx] = value;
+            }
+        }
+        Some(solution)
+    }
+}
+
+impl Matrix {
+    pub fn identity(size: usize, modulus: i32) -> Self {
+        // Create a size x size identity matrix over GF(modulus)
+        let rows = (0..size)
+            .map(|row_idx| {
+                (0..size)
+                    .map(|col_idx| GFElement::new((row_idx == col_idx) as i32, modulus))
+                    .collect_vec()
+            })
             .collect_vec();
+        Matrix::new(rows)
+    }
 
-        Self::new(rows)
+    pub fn from_adjacency(adj: &[Vec<usize>], modulus: i32) -> Self {
+        // Create the toggle matrix A + I of Lights Out on a graph given as an
+        // adjacency list: pressing vertex j toggles j and its neighbours adj[j],
+        // so column j holds the vertices toggled by button j
+        let size = adj.len();
+        assert!(
+            adj.iter().flatten().all(|&neighbour| neighbour < size),
+            "Neighbour index out of bounds"
+        );
+        let mut matrix = Matrix::identity(size, modulus);
+        for (vertex, neighbours) in adj.iter().enumerate() {
+            for &neighbour in neighbours {
+                matrix.rows[neighbour][vertex] = GFElement::new(1, modulus);
+            }
+        }
+        matrix
     }
 
-    fn every_row_has_a_pivot(&self) -> bool {
-        // Check whether every row has a pivot (leading 1 in coefficient part)
-        // An all-zeros row is considered to have a pivot
-        // A non-zero row has a pivot if the first non-zero element in the coefficient
-        // part (excluding the last column) is 1
-        self.rows.iter().all(|row| {
-            row.iter().all(|x| x.value == 0)
-                || row
-                    .iter()
-                    .take(row.len())
-                    .find(|x| x.value != 0)
-                    .map_or(false, |x| x.value == 1)
-        })
+    pub fn parse(s: &str, modulus: i32) -> Result<Self, ParseError> {
+        // Parse a matrix from text with one row per line and whitespace separated
+        // integers, which are reduced modulo modulus. Blank lines are ignored.
+        Matrix::from_reader(s.as_bytes(), modulus)
     }
 
-    fn every_column_has_a_pivot(&self) -> bool {
-        // Transpose the matrix and check whether every row has a pivot
-        // The augmentation of the matrix is ignored
+    pub fn from_reader(reader: impl BufRead, modulus: i32) -> Result<Self, ParseError> {
+        // Parse a matrix in the format of parse line by line, without reading
+        // the whole input into memory first. Errors report the line they occur
+        // on, counting blank lines as well.
+        GFElement::try_new(0, modulus)?;
 
-        self.transpose().every_row_has_a_pivot()
-    }
+        let mut rows: Vec<Vec<GFElement>> = Vec::new();
+        for (line_idx, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| ParseError::Io(err.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let row = line
+                .split_whitespace()
+                .enumerate()
+                .map(|(col_idx, token)| {
+                    GFElement::parse(token, modulus).map_err(|_| ParseError::InvalidNumber {
+                        row: line_idx,
+                        col: col_idx,
+                        found: token.to_string(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if let Some(first_row) = rows.first()
+                && first_row.len() != row.len()
+            {
+                return Err(ParseError::RaggedRow {
+                    row: line_idx,
+                    expected: first_row.len(),
+                    found: row.len(),
+                });
+            }
+            rows.push(row);
+        }
 
-    fn transpose(&self) -> Self {
-        // Return a new transposed matrix
+        if rows.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        Ok(Matrix::new(rows))
+    }
 
-        let rows = (0..self
-            .rows
-            .first()
-            .expect("Matrix should have one row at least")
-            .len())
-            .map(|col_idx| {
-                (0..self.rows.len())
-                    .map(|row_idx| {
-                        self.rows[col_idx][row_idx] // Transposition occurs here
+    pub fn random(n_rows: usize, n_cols: usize, modulus: i32, seed: u64) -> Self {
+        // Create a matrix of uniformly distributed elements over GF(modulus).
+        // The same seed always yields the same matrix, which keeps tests and
+        // benchmarks reproducible.
+        let mut state = seed;
+        let rows = (0..n_rows)
+            .map(|_| {
+                (0..n_cols)
+                    .map(|_| {
+                        let value = splitmix64(&mut state) % modulus as u64;
+                        GFElement::new(value as i32, modulus)
                     })
                     .collect_vec()
             })
             .collect_vec();
+        Matrix::new(rows)
+    }
 
-        Self::new(rows)
+    pub fn rows(&self) -> &[Vec<GFElement>] {
+        // Borrow the rows of the matrix
+        &self.rows
     }
 
-    fn is_any_row_unsolvable(&self) -> bool {
-        // Any row of the form (0,0,0,...,k) is unsolvable for k<>0.
-        // This would correspond to a non zero value being the result of a sum
-        // of values multiplied by 0.
-        self.rows.iter().any(|row| {
-            row.iter().take(row.len() - 1).all(|x| x.value == 0)
-                && row.last().map_or(false, |x| x.value != 0)
-        })
+    pub fn into_rows(self) -> Vec<Vec<GFElement>> {
+        // Take the rows out of the matrix
+        self.rows
     }
 
-    pub fn solution(&self) -> Option<Vec<GFElement>> {
-        // If the Puzzle is solvable, return the last column of the RREF form matrix
-        // NOTE: The matrix is turned to RREF format multiple times, once in is_solvable
-        // and once explicitly after. This is to not have to verify if it is in RREF form.
-        if !self.is_solvable() {
-            return None;
+    pub fn nonzero_count(&self) -> usize {
+        // Number of non-zero elements
+        self.rows.iter().flatten().filter(|x| x.value != 0).count()
+    }
+
+    pub fn density(&self) -> f64 {
+        // Fraction of non-zero elements, 0 for an empty matrix
+        let n_elements = self.rows.iter().map(|row| row.len()).sum::<usize>();
+        if n_elements == 0 {
+            return 0.0;
         }
+        self.nonzero_count() as f64 / n_elements as f64
+    }
 
-        let augmentation = self
-            .to_rref()
+    pub fn modulus(&self) -> Option<i32> {
+        // The modulus of the first element, None for an empty matrix
+        self.rows
+            .first()
+            .and_then(|row| row.first())
+            .map(|element| element.modulus)
+    }
+
+    fn assert_uniform_modulus(&self) {
+        // Panic naming the first element whose modulus differs from the modulus of
+        // the matrix, instead of failing somewhere inside the element arithmetic
+        let Some(expected) = self.modulus() else {
+            return;
+        };
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            if let Some(col_idx) = row.iter().position(|x| x.modulus != expected) {
+                panic!(
+                    "Matrix has mixed moduli: row {} col {} has modulus {}, expected {}",
+                    row_idx, col_idx, row[col_idx].modulus, expected
+                );
+            }
+        }
+    }
+
+    fn check_same_modulus(&self, other: &Matrix) -> Result<(), LinAlgError> {
+        // Verify that both matrices are defined over the same field
+        match (self.modulus(), other.modulus()) {
+            (Some(expected), Some(found)) if expected != found => {
+                Err(LinAlgError::ModulusMismatch { expected, found })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn map(&self, f: impl Fn(GFElement) -> GFElement) -> Matrix {
+        // Apply f to every element, keeping the shape of the matrix
+        let rows = self
             .rows
             .iter()
-            .map(|row| row.iter().copied().last().expect("Empty row not expected"))
+            .map(|row| row.iter().map(|&element| f(element)).collect_vec())
             .collect_vec();
-        Some(augmentation)
+        Matrix::new(rows)
     }
-}
 
-impl Display for Matrix {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // Convert numbers into strings
-        let vals = self
+    pub fn with_modulus(&self, new_modulus: i32) -> Matrix {
+        // Reinterpret the values over GF(new_modulus), reducing them if needed.
+        // This only keeps the meaning of the matrix if the fields are compatible,
+        // e.g. lifting values 0 and 1 from GF(2); in general arithmetic done over
+        // the old field does not carry over to the new one.
+        self.map(|element| GFElement::new(element.value, new_modulus))
+    }
+
+    pub fn concat_vertical(&self, other: &Matrix) -> Result<Matrix, LinAlgError> {
+        // Stack the rows of other below the rows of self
+        if self.n_cols() != other.n_cols() {
+            return Err(LinAlgError::DimensionMismatch {
+                expected: self.n_cols(),
+                found: other.n_cols(),
+            });
+        }
+        self.check_same_modulus(other)?;
+
+        let rows = self
             .rows
             .iter()
-            .map(|row| {
-                row.iter()
-                    .map(|element| format!("{}", element))
-                    .collect_vec()
-            })
+            .chain(other.rows.iter())
+            .cloned()
             .collect_vec();
+        Ok(Matrix::new(rows))
+    }
+
+    pub fn concat_horizontal(&self, other: &Matrix) -> Result<Matrix, LinAlgError> {
+        // Append the columns of other to the right of the columns of self
+        if self.n_rows() != other.n_rows() {
+            return Err(LinAlgError::DimensionMismatch {
+                expected: self.n_rows(),
+                found: other.n_rows(),
+            });
+        }
+        self.check_same_modulus(other)?;
 
-        // Determine widest string's len()
-        let max_len = vals
+        let rows = self
+            .rows
             .iter()
-            .flat_map(|row| row.iter().map(|element| element.len()))
-            .max()
-            .unwrap_or(0);
+            .zip(other.rows.iter())
+            .map(|(left, right)| left.iter().chain(right.iter()).copied().collect_vec())
+            .collect_vec();
+        Ok(Matrix::new(rows))
+    }
 
-        // Left pad elements and collect into space separated columns of LF separated rows
-        let output = vals
+    pub fn pad(&self, rows: usize, cols: usize) -> Result<Matrix, LinAlgError> {
+        // Extend the matrix to rows x cols by appending zero rows and columns.
+        // Padding cannot shrink the matrix, a smaller size is reported as a
+        // dimension mismatch against the current size. The modulus of the zeros
+        // is taken from the elements, so an empty matrix cannot be padded.
+        for (requested, current) in [(rows, self.n_rows()), (cols, self.n_cols())] {
+            if requested < current {
+                return Err(LinAlgError::DimensionMismatch {
+                    expected: current,
+                    found: requested,
+                });
+            }
+        }
+        let zero = GFElement::new(0, self.modulus().ok_or(LinAlgError::Empty)?);
+
+        let mut padded = self
+            .rows
             .iter()
             .map(|row| {
                 row.iter()
-                    .map(|element| format!("{:>width$}", element, width = max_len))
+                    .copied()
+                    .chain(iter::repeat_n(zero, cols - row.len()))
                     .collect_vec()
-                    .join(" ")
             })
-            .collect_vec()
-            .join("\n");
+            .collect_vec();
+        padded.resize(rows, vec![zero; cols]);
+        Ok(Matrix::new(padded))
+    }
 
-        write!(f, "{}", output)
+    pub fn swap_columns(&mut self, a: usize, b: usize) {
+        // Exchange two columns in place, the column counterpart of a row swap.
+        // Swapping columns reorders the variables, so a solution of the new
+        // system has entries a and b swapped as well.
+        for row in self.rows.iter_mut() {
+            row.swap(a, b);
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::Matrix;
-    use crate::finite_field::GFElement;
-    use itertools::Itertools;
-    use rstest::rstest;
+    pub fn submatrix(&self, rows: Range<usize>, cols: Range<usize>) -> Result<Matrix, LinAlgError> {
+        // Extract the rectangular block of the given row and column ranges
+        for (range, len) in [(&rows, self.n_rows()), (&cols, self.n_cols())] {
+            if range.start > range.end || range.end > len {
+                return Err(LinAlgError::OutOfBounds {
+                    range: range.clone(),
+                    len,
+                });
+            }
+        }
 
-    #[test]
-    fn test_matrix_display() {
-        // Construct small matrix
-        //  0 1
-        //  1 2
-        let rows = (0..=1)
-            .map(|row_idx| {
-                (0..=1)
-                    .map(|col_idx| GFElement::new(row_idx + col_idx, 3))
+        let block = self.rows[rows]
+            .iter()
+            .map(|row| row[cols.clone()].to_vec())
+            .collect_vec();
+        Ok(Matrix::new(block))
+    }
+
+    pub fn multiply(&self, other: &Matrix) -> Result<Matrix, LinAlgError> {
+        // Return the matrix product self * other
+        if self.n_cols() != other.n_rows() {
+            return Err(LinAlgError::DimensionMismatch {
+                expected: self.n_cols(),
+                found: other.n_rows(),
+            });
+        }
+        self.check_same_modulus(other)?;
+
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                (0..other.n_cols())
+                    .map(|col_idx| {
+                        row.iter()
+                            .zip(other.rows.iter())
+                            .map(|(element, other_row)| *element * other_row[col_idx])
+                            .reduce(|acc, product| acc + product)
+                            .expect("Matrix should have at least one column")
+                    })
                     .collect_vec()
             })
             .collect_vec();
-        let matrix = Matrix::new(rows);
-        assert_eq!(format!("{}", matrix), "0 1\n1 2");
+        Ok(Matrix::new(rows))
     }
 
-    #[test]
-    fn test_rref() {
-        // Construct small matrix
-        //  0 1
-        //  1 2
-        let rows = (0..=1)
-            .map(|row_idx| {
-                (0..=1)
-                    .map(|col_idx| GFElement::new(row_idx + col_idx, 3))
+    pub fn kronecker(&self, other: &Matrix) -> Result<Matrix, LinAlgError> {
+        // Return the Kronecker product: the block matrix where block (i, j) is
+        // other scaled by self[i][j]
+        self.check_same_modulus(other)?;
+
+        let rows = self
+            .rows
+            .iter()
+            .cartesian_product(other.rows.iter())
+            .map(|(row, other_row)| {
+                row.iter()
+                    .cartesian_product(other_row.iter())
+                    .map(|(element, other_element)| *element * *other_element)
                     .collect_vec()
             })
             .collect_vec();
-        let matrix = Matrix::new(rows);
-        // Verify reduced row_echelon_form is correct
-        let matrix_rref = matrix.to_rref();
-        assert_eq!(format!("{}", matrix_rref), "1 0\n0 1");
-        // Verify that rref form stays
-        assert_eq!(format!("{}", matrix_rref.to_rref()), "1 0\n0 1");
+        Ok(Matrix::new(rows))
     }
 
-    #[test]
-    fn test_rref_larger_matrix() {
-        // Construct a 3x4 matrix
-        //  1 2 3 4
-        //  0 1 2 3
-        //  1 1 1 1
-        let rows = vec![
-            vec![
-                GFElement::new(1, 5),
-                GFElement::new(2, 5),
-                GFElement::new(3, 5),
-                GFElement::new(4, 5),
-            ],
-            vec![
-                GFElement::new(0, 5),
-                GFElement::new(1, 5),
-                GFElement::new(2, 5),
-                GFElement::new(3, 5),
-            ],
-            vec![
-                GFElement::new(1, 5),
-                GFElement::new(1, 5),
-                GFElement::new(1, 5),
-                GFElement::new(1, 5),
-            ],
-        ];
-        let matrix = Matrix::new(rows);
-        let matrix_rref = matrix.to_rref();
-
-        // Expected RREF:
-        //  1 0 4 3
-        //  0 1 2 3
-        //  0 0 0 0
-        assert_eq!(format!("{}", matrix_rref), "1 0 4 3\n0 1 2 3\n0 0 0 0");
-        // Verify that rref form stays
-        assert_eq!(
-            format!("{}", matrix_rref.to_rref()),
-            format!("{}", matrix_rref)
-        );
+    pub fn solve_for(&self, rhs: &[GFElement]) -> Result<Option<Vec<GFElement>>, LinAlgError> {
+        // Solve self * x = rhs for x, treating self as the coefficient matrix.
+        // Returns None if the system has no solution.
+        if let Some(expected) = self.modulus()
+            && let Some(element) = rhs.iter().find(|x| x.modulus != expected)
+        {
+            return Err(LinAlgError::ModulusMismatch {
+                expected,
+                found: element.modulus,
+            });
+        }
+        let column = Matrix::new(rhs.iter().map(|&x| vec![x]).collect_vec());
+        Ok(self.concat_horizontal(&column)?.solution())
     }
 
-    #[test]
-    fn test_solvable() {
-        // Test whether a matrix is marked as solvable
-        let rows = vec![
-            vec![
-                GFElement::new(1, 2),
+    pub fn decompose(&self) -> Option<Decomposition> {
+        // Factor the coefficient matrix as P * self = L * U with a row permutation
+        // P, a unit lower triangular L and an upper U in row echelon form, so that
+        // systems with many right hand sides are solved by substitution instead
+        // of reducing the matrix again for each of them. Singular and non-square
+        // matrices are supported. None for an empty matrix or if a pivot has no
+        // inverse (composite moduli).
+        let modulus = self.modulus()?;
+        let (n_rows, n_cols) = (self.n_rows(), self.n_cols());
+        let mut upper = self.rows.clone();
+        let mut lower = Matrix::identity(n_rows, modulus).rows;
+        let mut permutation = (0..n_rows).collect_vec();
+        let mut pivots = vec![];
+
+        for col_idx in 0..n_cols {
+            let pivot_row_idx = pivots.len();
+            if pivot_row_idx == n_rows {
+                break;
+            }
+            let Some(found_row_idx) =
+                (pivot_row_idx..n_rows).find(|&row_idx| upper[row_idx][col_idx].value != 0)
+            else {
+                continue;
+            };
+
+            // Swap the rows, including the multipliers already stored in L
+            upper.swap(pivot_row_idx, found_row_idx);
+            permutation.swap(pivot_row_idx, found_row_idx);
+            if found_row_idx != pivot_row_idx {
+                let (top, bottom) = lower.split_at_mut(found_row_idx);
+                top[pivot_row_idx][..pivot_row_idx]
+                    .swap_with_slice(&mut bottom[0][..pivot_row_idx]);
+            }
+
+            // Eliminate the column below the pivot, remembering the multipliers
+            let pivot_inverse = upper[pivot_row_idx][col_idx].inverse()?;
+            let pivot_row = upper[pivot_row_idx].clone();
+            for row_idx in pivot_row_idx + 1..n_rows {
+                let factor = upper[row_idx][col_idx] * pivot_inverse;
+                if factor.value == 0 {
+                    continue;
+                }
+                lower[row_idx][pivot_row_idx] = factor;
+                for (element, pivot_element) in upper[row_idx].iter_mut().zip(pivot_row.iter()) {
+                    *element = *element - factor * *pivot_element;
+                }
+            }
+            pivots.push(col_idx);
+        }
+
+        Some(Decomposition {
+            permutation,
+            lower: Matrix::new(lower),
+            upper: Matrix::new(upper),
+            pivots,
+        })
+    }
+
+    pub fn trace(&self) -> Result<GFElement, LinAlgError> {
+        // Sum of the diagonal elements of a square matrix. The empty matrix has
+        // no elements to take the modulus of its zero trace from.
+        if self.n_rows() != self.n_cols() {
+            return Err(LinAlgError::NotSquare {
+                n_rows: self.n_rows(),
+                n_cols: self.n_cols(),
+            });
+        }
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(idx, row)| row[idx])
+            .reduce(|acc, element| acc + element)
+            .ok_or(LinAlgError::Empty)
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        // Check whether the matrix is square and equal to its transpose
+        self.n_rows() == self.n_cols()
+            && self.rows.iter().enumerate().all(|(row_idx, row)| {
+                row.iter()
+                    .enumerate()
+                    .all(|(col_idx, element)| *element == self.rows[col_idx][row_idx])
+            })
+    }
+
+    pub fn is_toggle_matrix(&self) -> bool {
+        // Check whether the coefficient part of the augmented matrix describes a
+        // valid Lights Out system: it is square, symmetric (pressing a toggles b
+        // exactly when pressing b toggles a) and every cell toggles itself
+        let coefficients = self.coefficient_columns();
+        coefficients.is_symmetric()
+            && coefficients
+                .rows
+                .iter()
+                .enumerate()
+                .all(|(idx, row)| row[idx].value != 0)
+    }
+
+    pub fn to_rref(&self) -> Matrix {
+        // Return the reduced row echelon form of the matrix, see to_rref_mut
+        let mut matrix = self.clone();
+        matrix.to_rref_mut();
+        matrix
+    }
+
+    pub fn to_rref_mut(&mut self) {
+        // Convert the matrix to reduced row echelon form in place.
+        // The result is canonical: pivots strictly advance column by column down the
+        // rows and all zero rows end up at the bottom, so row-equivalent matrices
+        // reduce to the same matrix.
+        self.assert_uniform_modulus();
+        let n_cols = self.n_cols();
+        self.reduce(n_cols, |_| {}, |_, _| {});
+    }
+
+    pub fn to_rref_with_progress(&self, cb: impl FnMut(usize, usize)) -> Matrix {
+        // Convert the matrix to reduced row echelon form, calling cb with
+        // (pivot rows done, total rows) after each pivot step
+        self.assert_uniform_modulus();
+        let mut rref = self.clone();
+        let n_cols = rref.n_cols();
+        rref.reduce(n_cols, |_| {}, cb);
+        rref
+    }
+
+    pub fn rref_steps(&self) -> (Matrix, Vec<RowOp>) {
+        // Return the RREF form of the matrix together with the elementary row
+        // operations leading to it. Applying the operations in order to the
+        // matrix reproduces the RREF form.
+        self.assert_uniform_modulus();
+        let mut rref = self.clone();
+        let mut steps = vec![];
+        let n_cols = rref.n_cols();
+        rref.reduce(n_cols, |op| steps.push(op), |_, _| {});
+        (rref, steps)
+    }
+
+    pub fn apply_row_op(&mut self, op: &RowOp) {
+        // Apply a single elementary row operation in place
+        match *op {
+            RowOp::Swap(a, b) => self.rows.swap(a, b),
+            RowOp::Scale(row_idx, factor) => {
+                for element in self.rows[row_idx].iter_mut() {
+                    *element = *element * factor;
+                }
+            }
+            RowOp::AddScaled {
+                target,
+                source,
+                factor,
+            } => {
+                let source_row = self.rows[source].clone();
+                for (element, source_element) in self.rows[target].iter_mut().zip(source_row) {
+                    *element = *element + factor * source_element;
+                }
+            }
+        }
+    }
+
+    pub fn rref_with_transform(&self) -> (Matrix, Matrix) {
+        // Return the RREF form of the matrix together with the matrix E of the
+        // row operations, so that E * self == RREF. E is found by applying the
+        // same operations to the identity, i.e. by reducing [self | I] to [RREF | E].
+        let (n_rows, n_cols) = (self.n_rows(), self.n_cols());
+        let modulus = self
+            .modulus()
+            .expect("Matrix should have at least one element");
+
+        let mut augmented = self
+            .concat_horizontal(&Matrix::identity(n_rows, modulus))
+            .expect("Identity has one row per row of the matrix");
+        augmented.to_rref_mut();
+
+        let rref = augmented
+            .submatrix(0..n_rows, 0..n_cols)
+            .expect("Left block lies within the matrix");
+        let transform = augmented
+            .submatrix(0..n_rows, n_cols..n_cols + n_rows)
+            .expect("Right block lies within the matrix");
+        (rref, transform)
+    }
+
+    pub fn dependent_rows(&self) -> Vec<usize> {
+        // Indices of the rows that are linear combinations of earlier rows, all
+        // columns are considered. These are the columns of the transpose without
+        // a pivot, as RREF picks the earliest independent columns as pivots.
+        let pivots = self
+            .transpose()
+            .to_rref()
+            .rows
+            .iter()
+            .filter_map(|row| row.iter().position(|x| x.value != 0))
+            .collect_vec();
+        (0..self.n_rows())
+            .filter(|row_idx| !pivots.contains(row_idx))
+            .collect_vec()
+    }
+
+    pub fn is_row_equivalent(&self, other: &Matrix) -> bool {
+        // Whether other can be obtained from self by elementary row operations,
+        // i.e. both have the same RREF form. Matrices of different shape or
+        // modulus are never row-equivalent.
+        self.n_rows() == other.n_rows()
+            && self.n_cols() == other.n_cols()
+            && self.modulus() == other.modulus()
+            && self.to_rref() == other.to_rref()
+    }
+
+    pub fn to_latex(&self) -> String {
+        // Render the matrix as a LaTeX pmatrix environment
+        format!(
+            "\\begin{{pmatrix}}\n{}\n\\end{{pmatrix}}",
+            self.latex_rows()
+        )
+    }
+
+    pub fn to_latex_augmented(&self) -> String {
+        // Render the augmented matrix as a LaTeX array in parentheses, with a
+        // vertical bar in front of the last column
+        let column_spec = format!("{}|c", "c".repeat(self.n_cols().saturating_sub(1)));
+        format!(
+            "\\left(\\begin{{array}}{{{}}}\n{}\n\\end{{array}}\\right)",
+            column_spec,
+            self.latex_rows()
+        )
+    }
+
+    fn latex_rows(&self) -> String {
+        // The rows of a LaTeX matrix body, entries separated by & and rows by \\
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(|element| element.to_string()).join(" & "))
+            .join(" \\\\\n")
+    }
+
+    pub fn rank(&self) -> usize {
+        // Number of non-zero rows in the RREF form, all columns are considered
+        self.to_rref()
+            .rows
+            .iter()
+            .filter(|row| row.iter().any(|x| x.value != 0))
+            .count()
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        // Determine whether the system of the augmented matrix has at least one
+        // solution, i.e. its RREF form has no row of the form (0,0,...,0,k) with
+        // k<>0. Whether the solution is unique is not considered, that is the case
+        // if there are no free_columns.
+        !self.to_rref().is_any_row_unsolvable()
+    }
+
+    pub fn is_solvable(&self) -> bool {
+        // Determine whether the puzzle corresponding to the matrix is solvable.
+        // It will be assumed that the right most column is the target vector of
+        // the augmented matrix
+        // NOTE: Kept for backward compatibility. Despite the additional pivot check
+        // it does not test for a unique solution: every non-zero row of an RREF
+        // matrix starts with a pivot, so this agrees with is_consistent.
+
+        let matrix_rref = self.to_rref();
+
+        // First, check whether any row is unsolvable
+        if matrix_rref.is_any_row_unsolvable() {
+            return false;
+        }
+
+        // If no row is unsolvable and every row has a pivot, the puzzle is
+        // solvable
+        matrix_rref.coefficient_columns().every_row_has_a_pivot()
+    }
+
+    pub fn solve_mod_prime_power(&self, p: i32, k: u32) -> Option<Vec<i32>> {
+        // Solve the augmented system over Z/(p^k), where division by multiples
+        // of p is impossible and reduction fails. Every non-zero element is a
+        // unit times a power of p, so eliminating with the pivot of lowest
+        // p-valuation leaves all other entries of its row and column divisible
+        // by that power. Free variables are set to 0.
+        assert!(k >= 1, "Exponent should be at least 1");
+        let modulus = p
+            .checked_pow(k)
+            .expect("Prime power should fit into an i32") as i64;
+        assert_eq!(
+            self.modulus(),
+            Some(modulus as i32),
+            "Matrix should be over Z/(p^k)"
+        );
+        let valuation = |value: i64| {
+            // Exponent of the largest power of p dividing a non-zero value
+            let mut value = value;
+            let mut exponent = 0;
+            while value % p as i64 == 0 {
+                value /= p as i64;
+                exponent += 1;
+            }
+            exponent
+        };
+        let unit_inverse = |value: i64| {
+            // Extended Euclid on value and p^k, which are coprime
+            let (mut r0, mut r1) = (modulus, value.rem_euclid(modulus));
+            let (mut s0, mut s1) = (0i64, 1i64);
+            while r1 != 0 {
+                let quotient = r0 / r1;
+                (r0, r1) = (r1, r0 - quotient * r1);
+                (s0, s1) = (s1, s0 - quotient * s1);
+            }
+            assert_eq!(r0, 1, "Value coprime to p is a unit mod p^k");
+            s0.rem_euclid(modulus)
+        };
+        let mut rows = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|x| x.value as i64).collect_vec())
+            .collect_vec();
+        let n_vars = self.n_cols() - 1;
+
+        // Forward elimination, pivots are (column, power of p, inverse of the unit)
+        let mut pivots: Vec<(usize, i64, i64)> = vec![];
+        let mut used = vec![false; n_vars];
+        while pivots.len() < rows.len() {
+            let r = pivots.len();
+            let Some((row, col)) = (r..rows.len())
+                .cartesian_product((0..n_vars).filter(|&col| !used[col]))
+                .filter(|&(row, col)| rows[row][col] != 0)
+                .min_by_key(|&(row, col)| valuation(rows[row][col]))
+            else {
+                break;
+            };
+            rows.swap(r, row);
+            used[col] = true;
+            let power = (p as i64).pow(valuation(rows[r][col]));
+            let inverse = unit_inverse(rows[r][col] / power);
+            let pivot_row = rows[r].clone();
+            for row in rows.iter_mut().skip(r + 1) {
+                let factor = row[col] / power * inverse % modulus;
+                for (x, y) in row.iter_mut().zip(pivot_row.iter()) {
+                    *x = (*x - factor * y).rem_euclid(modulus);
+                }
+            }
+            pivots.push((col, power, inverse));
+        }
+
+        // Rows without a pivot have only zero coefficients
+        if rows[pivots.len()..].iter().any(|row| row[n_vars] != 0) {
+            return None;
+        }
+
+        // Back substitution, the remaining coefficients of a pivot row are
+        // divisible by its power of p, so the right hand side has to be as well
+        let mut solution = vec![0i64; n_vars];
+        for (r, &(col, power, inverse)) in pivots.iter().enumerate().rev() {
+            // Reduce every product and the running sum, p^k can be close to 2^31
+            let product = rows[r]
+                .iter()
+                .zip(solution.iter())
+                .fold(0, |acc, (a, x)| (acc + a * x % modulus) % modulus);
+            let residual = (rows[r][n_vars] - product).rem_euclid(modulus);
+            if residual % power != 0 {
+                return None;
+            }
+            solution[col] = residual / power * inverse % (modulus / power);
+        }
+        Some(solution.into_iter().map(|x| x as i32).collect_vec())
+    }
+
+    pub fn coefficient_columns(&self) -> Self {
+        // Create a new matrix without the last column (the augmentation)
+        assert!(self.n_cols() > 0, "Matrix should have at least one column");
+        self.submatrix(0..self.n_rows(), 0..self.n_cols() - 1)
+            .expect("Coefficient block lies within the matrix")
+    }
+
+    fn every_row_has_a_pivot(&self) -> bool {
+        // Check whether every row has a pivot (leading 1 in coefficient part)
+        // An all-zeros row is considered to have a pivot
+        // A non-zero row has a pivot if the first non-zero element in the coefficient
+        // part (excluding the last column) is 1
+        self.rows.iter().all(|row| {
+            row.iter().all(|x| x.value == 0)
+                || row
+                    .iter()
+                    .take(row.len())
+                    .find(|x| x.value != 0)
+                    .is_some_and(|x| x.value == 1)
+        })
+    }
+
+    // Not used by the library itself yet, only by the tests
+    #[allow(dead_code)]
+    fn every_column_has_a_pivot(&self) -> bool {
+        // Transpose the matrix and check whether every row has a pivot
+        // The augmentation of the matrix is ignored
+
+        self.transpose().every_row_has_a_pivot()
+    }
+
+    pub fn trim_zero_rows(&self) -> Matrix {
+        // Remove all rows consisting only of zeros, e.g. the bottom rows of a
+        // rank deficient RREF form
+        let rows = self
+            .rows
+            .iter()
+            .filter(|row| row.iter().any(|x| x.value != 0))
+            .cloned()
+            .collect_vec();
+        Matrix::new(rows)
+    }
+
+    pub fn normalized(&self) -> Matrix {
+        // Clean up the rows without reducing the matrix: zero rows are dropped,
+        // every other row is scaled to a leading coefficient of 1 and rows that
+        // then repeat an earlier row (scaled duplicates) are dropped as well.
+        // The result has the same row space. If every row is zero it is empty.
+        let mut rows: Vec<Vec<GFElement>> = vec![];
+        for row in self.rows.iter() {
+            let Some(lead) = row.iter().find(|x| x.value != 0) else {
+                continue;
+            };
+            let scaled = row.iter().map(|x| *x / *lead).collect_vec();
+            if !rows.contains(&scaled) {
+                rows.push(scaled);
+            }
+        }
+        Matrix::new(rows)
+    }
+
+    pub(crate) fn transpose(&self) -> Self {
+        // Return a new transposed matrix
+
+        let rows = (0..self
+            .rows
+            .first()
+            .expect("Matrix should have one row at least")
+            .len())
+            .map(|col_idx| {
+                (0..self.rows.len())
+                    .map(|row_idx| {
+                        self.rows[row_idx][col_idx] // Transposition occurs here
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        Self::new(rows)
+    }
+
+    pub fn inconsistent_rows(&self) -> Vec<usize> {
+        // Indices of all rows (0,0,0,...,k) with k<>0 in the reduced matrix, each
+        // a contradiction of the system. Only the coefficient columns are pivoted
+        // like in solution, a pivot in the augmentation would leave just one.
+        self.solve_rref()
+            .rows
+            .iter()
+            .positions(|row| {
+                row.iter().take(row.len() - 1).all(|x| x.value == 0)
+                    && row.last().is_some_and(|x| x.value != 0)
+            })
+            .collect_vec()
+    }
+
+    pub fn solution_bools(&self) -> Option<Vec<bool>> {
+        // The solution of a GF(2) system as booleans, true meaning 1
+        assert_eq!(
+            self.modulus(),
+            Some(2),
+            "Boolean solutions need a GF(2) matrix"
+        );
+        self.solution()
+            .map(|solution| solution.iter().map(|x| x.value == 1).collect_vec())
+    }
+
+    pub fn pivot_columns(&self) -> Vec<usize> {
+        // Indices of the columns holding a pivot in the RREF form, the
+        // augmentation is excluded
+        self.coefficient_columns()
+            .to_rref()
+            .rows
+            .iter()
+            .filter_map(|row| row.iter().position(|x| x.value != 0))
+            .collect_vec()
+    }
+
+    pub fn free_columns(&self) -> Vec<usize> {
+        // Indices of the columns without a pivot in the RREF form, i.e. the free
+        // variables of the system. The augmentation is excluded.
+        let pivots = self.pivot_columns();
+        (0..self.n_cols() - 1)
+            .filter(|col_idx| !pivots.contains(col_idx))
+            .collect_vec()
+    }
+
+    pub fn null_space(&self) -> Vec<Vec<GFElement>> {
+        // Return a basis of the solutions of the homogeneous system, i.e. of the
+        // kernel of the coefficient part. The augmentation is ignored.
+        // Every free variable (column without a pivot) yields one basis vector:
+        // the free variable is set to 1 and each pivot variable to the negated
+        // coefficient of the free variable in the pivot's row.
+        let matrix_rref = self.coefficient_columns().to_rref();
+        let n_vars = matrix_rref.n_cols();
+        let modulus = self
+            .modulus()
+            .expect("Matrix should have at least one element");
+
+        let pivots = matrix_rref
+            .rows
+            .iter()
+            .filter_map(|row| row.iter().position(|x| x.value != 0))
+            .collect_vec();
+
+        (0..n_vars)
+            .filter(|col_idx| !pivots.contains(col_idx))
+            .map(|free_idx| {
+                let mut vector = vec![GFElement::new(0, modulus); n_vars];
+                vector[free_idx] = GFElement::new(1, modulus);
+                for (row, &pivot_idx) in matrix_rref.rows.iter().zip(pivots.iter()) {
+                    vector[pivot_idx] = -row[free_idx];
+                }
+                vector
+            })
+            .collect_vec()
+    }
+
+    pub fn parametric_solution(&self) -> Option<ParametricSolution> {
+        // Return the general solution: the particular solution plus, for every
+        // free variable, the vector it is multiplied with. None if unsolvable.
+        let particular = self.solution()?;
+        let free = self
+            .free_columns()
+            .into_iter()
+            .zip(self.null_space())
+            .collect_vec();
+        Some(ParametricSolution { particular, free })
+    }
+
+    pub fn solution_count(&self) -> u128 {
+        // Number of solutions: modulus^k for k free variables, 0 if unsolvable
+        if !self.is_consistent() {
+            return 0;
+        }
+        let modulus = self
+            .modulus()
+            .expect("Matrix should have at least one element");
+        (modulus as u128)
+            .checked_pow(self.free_columns().len() as u32)
+            .expect("Solution count should fit into a u128")
+    }
+
+    pub fn solutions_iter(&self) -> impl Iterator<Item = Vec<GFElement>> {
+        // Lazily enumerate all solutions by adding every combination of multiples
+        // of the null space basis vectors to the particular solution. Nothing is
+        // yielded if the system is unsolvable.
+        let Some(particular) = self.solution() else {
+            return Either::Left(iter::empty());
+        };
+        let basis = self.null_space();
+        let modulus = self
+            .modulus()
+            .expect("Matrix should have at least one element");
+
+        let solutions = (0..basis.len())
+            .map(|_| 0..modulus)
+            .multi_cartesian_product()
+            .map(move |factors| {
+                let mut candidate = particular.clone();
+                for (factor, vector) in factors.iter().zip(basis.iter()) {
+                    let factor = GFElement::new(*factor, modulus);
+                    for (element, basis_element) in candidate.iter_mut().zip(vector.iter()) {
+                        *element = *element + factor * *basis_element;
+                    }
+                }
+                candidate
+            });
+        Either::Right(solutions)
+    }
+
+    pub fn canonical_solution(&self) -> Option<Vec<GFElement>> {
+        // The lexicographically smallest solution, a canonical representative of
+        // all solutions. Reading the solution off the RREF sets the free variables
+        // to 0, but the pivots are taken from the left, so early variables are
+        // fixed by later free ones. With the coefficient columns reversed the
+        // pivots are taken from the right: every variable is then either free
+        // (set to the smallest value 0) or fixed by earlier free variables, which
+        // is the greedy choice of the smallest vector.
+        let n_vars = self.n_cols() - 1;
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                row[..n_vars]
+                    .iter()
+                    .rev()
+                    .chain([&row[n_vars]])
+                    .copied()
+                    .collect_vec()
+            })
+            .collect_vec();
+        let mut solution = Matrix::new(rows).solution()?;
+        solution.reverse();
+        Some(solution)
+    }
+
+    pub fn minimum_solution(&self) -> Option<Vec<GFElement>> {
+        // Return the solution with the smallest sum of values, which for a puzzle is
+        // the solution with the fewest button presses. All modulus^k solutions for
+        // k free variables are enumerated, so this is only suitable for small null
+        // spaces.
+        // Ties are broken by taking the lexicographically smallest solution
+        // (comparing values left to right), so the result does not depend on the
+        // order of enumeration.
+        self.solutions_iter()
+            .min_by_key(|candidate| minimum_key(candidate))
+    }
+
+    pub fn minimum_weighted_solution(&self, costs: &[u32]) -> Option<(Vec<GFElement>, u32)> {
+        // Return the GF(2) solution with the smallest total cost of the variables
+        // set to 1, together with that cost. Ties are broken like in
+        // minimum_solution. All solutions are enumerated.
+        assert_eq!(
+            self.modulus(),
+            Some(2),
+            "Weighted solutions need a GF(2) matrix"
+        );
+        assert_eq!(
+            costs.len(),
+            self.n_cols() - 1,
+            "Costs should have one entry per variable"
+        );
+        let cost = |candidate: &[GFElement]| {
+            candidate
+                .iter()
+                .zip(costs.iter())
+                .filter(|(x, _)| x.value != 0)
+                .map(|(_, cost)| cost)
+                .sum::<u32>()
+        };
+        self.solutions_iter()
+            .min_by_key(|candidate| (cost(candidate), minimum_key(candidate)))
+            .map(|best| {
+                let total = cost(&best);
+                (best, total)
+            })
+    }
+
+    pub fn minimum_solution_bounded(
+        &self,
+        max_candidates: usize,
+    ) -> Option<(Vec<GFElement>, bool)> {
+        // Like minimum_solution, but only the first max_candidates solutions (at
+        // least one) are compared. Returns the best solution found and whether
+        // all solutions were searched, i.e. whether it is the true minimum.
+        let mut solutions = self.solutions_iter();
+        let best = solutions
+            .by_ref()
+            .take(max_candidates.max(1))
+            .min_by_key(|candidate| minimum_key(candidate))?;
+        let exhaustive = solutions.next().is_none();
+        Some((best, exhaustive))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RowOp<F = GFElement> {
+    // An elementary row operation as recorded by rref_steps
+
+    // Exchange two rows
+    Swap(usize, usize),
+    // Multiply a row by a factor
+    Scale(usize, F),
+    // Add factor times the source row to the target row
+    AddScaled {
+        target: usize,
+        source: usize,
+        factor: F,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Decomposition {
+    // The factorization P * A = L * U of Matrix::decompose. Row i of P * A is row
+    // permutation[i] of A, pivots holds the pivot column of each non-zero row
+    // of U.
+    permutation: Vec<usize>,
+    lower: Matrix,
+    upper: Matrix,
+    pivots: Vec<usize>,
+}
+
+impl Decomposition {
+    pub fn solve(&self, rhs: &[GFElement]) -> Option<Vec<GFElement>> {
+        // Solve A * x = rhs, None if there is no solution. Like Matrix::solution
+        // the free variables are set to 0.
+        assert_eq!(
+            rhs.len(),
+            self.permutation.len(),
+            "Right hand side should have one entry per row"
+        );
+        let modulus = self
+            .upper
+            .modulus()
+            .expect("Decomposition of an empty matrix is not possible");
+
+        // Forward substitution L * y = P * rhs
+        let mut y: Vec<GFElement> = Vec::with_capacity(rhs.len());
+        for (row_idx, &source_idx) in self.permutation.iter().enumerate() {
+            let known = self.lower.rows[row_idx][..row_idx]
+                .iter()
+                .zip(y.iter())
+                .fold(GFElement::new(0, modulus), |acc, (l, y)| acc + *l * *y);
+            y.push(rhs[source_idx] - known);
+        }
+
+        // Rows of U without a pivot are zero, their target needs to be 0 as well
+        let rank = self.pivots.len();
+        if y[rank..].iter().any(|x| x.value != 0) {
+            return None;
+        }
+
+        // Back substitution U * x = y
+        let mut solution = vec![GFElement::new(0, modulus); self.upper.n_cols()];
+        for (row_idx, &pivot_idx) in self.pivots.iter().enumerate().rev() {
+            let row = &self.upper.rows[row_idx];
+            let known = row[pivot_idx + 1..]
+                .iter()
+                .zip(solution[pivot_idx + 1..].iter())
+                .fold(GFElement::new(0, modulus), |acc, (u, x)| acc + *u * *x);
+            solution[pivot_idx] = (y[row_idx] - known) / row[pivot_idx];
+        }
+        Some(solution)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParametricSolution {
+    // The general solution x = particular + sum(x_f * vector_f) of a system,
+    // where x_f are the free variables and vector_f the null space basis vector
+    // belonging to x_f
+    pub particular: Vec<GFElement>,
+    pub free: Vec<(usize, Vec<GFElement>)>,
+}
+
+impl Display for ParametricSolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // One line per variable, e.g. "x0 = 1 + 2*x2" for a pivot variable and
+        // "x2 free" for a free variable
+        let lines = self
+            .particular
+            .iter()
+            .enumerate()
+            .map(|(var_idx, constant)| {
+                if self.free.iter().any(|(free_idx, _)| *free_idx == var_idx) {
+                    return format!("x{} free", var_idx);
+                }
+                let terms = self
+                    .free
+                    .iter()
+                    .filter(|(_, vector)| vector[var_idx].value != 0)
+                    .map(|(free_idx, vector)| match vector[var_idx].value {
+                        1 => format!(" + x{}", free_idx),
+                        coefficient => format!(" + {}*x{}", coefficient, free_idx),
+                    })
+                    .join("");
+                format!("x{} = {}{}", var_idx, constant, terms)
+            })
+            .join("\n");
+
+        write!(f, "{}", lines)
+    }
+}
+
+fn minimum_key(candidate: &[GFElement]) -> (i32, Vec<i32>) {
+    // Order solutions by their sum of values, then lexicographically
+    let values = candidate.iter().map(|x| x.value).collect_vec();
+    (values.iter().sum::<i32>(), values)
+}
+
+pub fn dot(a: &[GFElement], b: &[GFElement]) -> GFElement {
+    // Dot product of two vectors over the same field. Over GF(2) multiplication
+    // is AND and the sum a parity, which is computed without field arithmetic.
+    assert_eq!(a.len(), b.len(), "Vectors should have the same length");
+    assert!(!a.is_empty(), "Vectors should not be empty");
+    let is_gf2 = a.iter().chain(b.iter()).all(|x| x.modulus == 2);
+    if is_gf2 {
+        dot_gf2(a, b)
+    } else {
+        dot_generic(a, b)
+    }
+}
+
+fn dot_generic(a: &[GFElement], b: &[GFElement]) -> GFElement {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| *x * *y)
+        .reduce(|acc, product| acc + product)
+        .expect("Vectors should not be empty")
+}
+
+fn dot_gf2(a: &[GFElement], b: &[GFElement]) -> GFElement {
+    let parity = a
+        .iter()
+        .zip(b.iter())
+        .filter(|(x, y)| x.value & y.value == 1)
+        .count()
+        % 2;
+    GFElement::new(parity as i32, 2)
+}
+
+pub fn xor_rows(target: &mut [GFElement], source: &[GFElement]) {
+    // Add source to target element-wise, which over GF(2) is an XOR of the
+    // values and done without field arithmetic
+    assert_eq!(
+        target.len(),
+        source.len(),
+        "Rows should have the same length"
+    );
+    let is_gf2 = target.iter().chain(source.iter()).all(|x| x.modulus == 2);
+    if is_gf2 {
+        xor_rows_gf2(target, source)
+    } else {
+        xor_rows_generic(target, source)
+    }
+}
+
+fn xor_rows_generic(target: &mut [GFElement], source: &[GFElement]) {
+    for (element, source_element) in target.iter_mut().zip(source.iter()) {
+        *element = *element + *source_element;
+    }
+}
+
+fn xor_rows_gf2(target: &mut [GFElement], source: &[GFElement]) {
+    for (element, source_element) in target.iter_mut().zip(source.iter()) {
+        element.value ^= source_element.value;
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    // Small seedable pseudo random number generator (SplitMix64)
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Display for Matrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Convert numbers into strings
+        let vals = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|element| format!("{}", element))
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        // Determine the widest string's len() per column. Rows of differing length
+        // only contribute to the columns they have.
+        let mut widths: Vec<usize> = Vec::new();
+        for row in vals.iter() {
+            if widths.len() < row.len() {
+                widths.resize(row.len(), 0);
+            }
+            for (width, element) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(element.len());
+            }
+        }
+
+        // Left pad elements and collect into space separated columns of LF separated rows
+        let output = vals
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(widths.iter())
+                    .map(|(element, width)| format!("{:>width$}", element, width = width))
+                    .collect_vec()
+                    .join(" ")
+            })
+            .collect_vec()
+            .join("\n");
+
+        write!(f, "{}", output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Matrix, RowOp, dot, dot_generic, dot_gf2, xor_rows, xor_rows_generic, xor_rows_gf2,
+    };
+    use crate::error::{GFError, LinAlgError, ParseError};
+    use crate::field::FieldElement;
+    use crate::finite_field::{GFElement, gcd};
+    use itertools::Itertools;
+    use proptest::prelude::*;
+    use rstest::rstest;
+    use std::io::Cursor;
+    use std::ops::{Add, Div, Mul, Neg, Sub};
+
+    fn matrix_from_values<R: AsRef<[i32]>>(values: &[R], modulus: i32) -> Matrix {
+        // Build a matrix from plain integers over GF(modulus)
+        Matrix::new(
+            values
+                .iter()
+                .map(|row| {
+                    row.as_ref()
+                        .iter()
+                        .map(|&value| GFElement::new(value, modulus))
+                        .collect_vec()
+                })
+                .collect_vec(),
+        )
+    }
+
+    #[test]
+    fn test_matrix_display() {
+        // Construct small matrix
+        //  0 1
+        //  1 2
+        let rows = (0..=1)
+            .map(|row_idx| {
+                (0..=1)
+                    .map(|col_idx| GFElement::new(row_idx + col_idx, 3))
+                    .collect_vec()
+            })
+            .collect_vec();
+        let matrix = Matrix::new(rows);
+        assert_eq!(format!("{}", matrix), "0 1\n1 2");
+    }
+
+    #[test]
+    fn test_transpose_non_square() {
+        // Every element moves from (row, col) to (col, row), also when the
+        // matrix is not square
+        let matrix = matrix_from_values(&[&[0, 1, 2], &[3, 4, 5]], 7);
+        let transposed = matrix.transpose();
+        assert_eq!((transposed.n_rows(), transposed.n_cols()), (3, 2));
+        assert_eq!(format!("{}", transposed), "0 3\n1 4\n2 5");
+        assert_eq!(format!("{}", transposed.transpose()), format!("{}", matrix));
+    }
+
+    #[test]
+    fn test_from_adjacency_cycle() {
+        // The cycle 0 - 1 - 2 - 3 - 0 toggles each vertex and its two neighbours
+        let adj = vec![vec![1, 3], vec![0, 2], vec![1, 3], vec![2, 0]];
+        let matrix = Matrix::from_adjacency(&adj, 2);
+        assert_eq!(format!("{}", matrix), "1 1 0 1\n1 1 1 0\n0 1 1 1\n1 0 1 1");
+        assert!(matrix.is_symmetric());
+    }
+
+    #[test]
+    fn test_default() {
+        let matrix = Matrix::default();
+        assert_eq!(matrix, Matrix::new(vec![]));
+        assert_eq!((matrix.n_rows(), matrix.n_cols()), (0, 0));
+        assert_eq!(matrix.modulus(), None);
+    }
+
+    #[test]
+    fn test_trim_zero_rows() {
+        let matrix = matrix_from_values(&[&[1, 2, 3], &[2, 4, 1], &[3, 1, 4], &[0, 0, 0]], 5);
+        let rref = matrix.to_rref();
+        let trimmed = rref.trim_zero_rows();
+        // The second and third rows are multiples of the first
+        assert_eq!(rref.n_rows() - trimmed.n_rows(), 3);
+        assert_eq!(trimmed.n_rows(), matrix.rank());
+        assert_eq!(format!("{}", trimmed), "1 2 3");
+    }
+
+    #[test]
+    fn test_normalized() {
+        // The second row is 2 times the first, the fourth row 3 times the third
+        let matrix = matrix_from_values(&[&[2, 4, 1], &[4, 3, 2], &[0, 0, 0], &[0, 3, 6]], 5);
+        let normalized = matrix.normalized();
+        assert_eq!(format!("{}", normalized), "1 2 3\n0 1 2");
+        assert_eq!(normalized.rank(), matrix.rank());
+    }
+
+    #[rstest]
+    // det = -5 is a unit mod 4, so x = (2, 3) is the only solution
+    #[case(vec![vec![1, 2, 0], vec![3, 1, 1]], 2, 2, Some(vec![2, 3]))]
+    #[case(vec![vec![1, 2, 0, 5], vec![0, 3, 4, 2], vec![2, 0, 1, 5]], 2, 3, Some(vec![1, 2, 3]))]
+    #[case(vec![vec![1, 3, 4], vec![2, 1, 0]], 3, 2, Some(vec![1, 7]))]
+    // 2 x = 1 has no solution mod 4, already failing mod 2
+    #[case(vec![vec![2, 1]], 2, 2, None)]
+    // 2 x = 2 mod 4 is solved by x = 1, although 0 x = 0 mod 2 suggests x = 0
+    #[case(vec![vec![2, 2]], 2, 2, Some(vec![1]))]
+    // p^k close to 2^31, products of entries do not fit into an i32 and sums of
+    // them not into an i64
+    #[case(
+        vec![
+            vec![3, 2147117568, 2147117568, 2147117568, 6],
+            vec![0, 2147117568, 2147117568, 2147117568, 9],
+            vec![0, 0, 5, 2147117568, 2147117558],
+            vec![0, 0, 0, 7, 2147117541],
+        ],
+        46337,
+        2,
+        Some(vec![2147117568, 2147117567, 2147117566, 2147117565])
+    )]
+    fn test_solve_mod_prime_power(
+        #[case] values: Vec<Vec<i32>>,
+        #[case] p: i32,
+        #[case] k: u32,
+        #[case] expected: Option<Vec<i32>>,
+    ) {
+        let matrix = matrix_from_values(&values, p.pow(k));
+        assert_eq!(matrix.solve_mod_prime_power(p, k), expected);
+    }
+
+    #[test]
+    fn test_solve_mod_prime_power_singular_toggle_matrix() {
+        // The 4x4 toggle matrix is singular mod 2, so eliminating mod 4 runs
+        // into pivots that are multiples of 2
+        let toggle = crate::puzzle::Puzzle::toggle_matrix(4, 4, 4);
+        for seed in 0..20 {
+            let presses = (0..16)
+                .map(|i| GFElement::new((i * seed + seed / 3) % 4, 4))
+                .collect_vec();
+            let target = toggle
+                .rows
+                .iter()
+                .map(|row| dot(row, &presses))
+                .collect_vec();
+            let augmented = Matrix::new(
+                toggle
+                    .rows
+                    .iter()
+                    .zip(target.iter())
+                    .map(|(row, b)| row.iter().copied().chain([*b]).collect_vec())
+                    .collect_vec(),
+            );
+            let solution = augmented
+                .solve_mod_prime_power(2, 2)
+                .expect("System built from a solution is solvable")
+                .into_iter()
+                .map(|x| GFElement::new(x, 4))
+                .collect_vec();
+            for (row, b) in toggle.rows.iter().zip(target.iter()) {
+                assert_eq!(dot(row, &solution), *b, "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rref() {
+        // Construct small matrix
+        //  0 1
+        //  1 2
+        let rows = (0..=1)
+            .map(|row_idx| {
+                (0..=1)
+                    .map(|col_idx| GFElement::new(row_idx + col_idx, 3))
+                    .collect_vec()
+            })
+            .collect_vec();
+        let matrix = Matrix::new(rows);
+        // Verify reduced row_echelon_form is correct
+        let matrix_rref = matrix.to_rref();
+        assert_eq!(format!("{}", matrix_rref), "1 0\n0 1");
+        // Verify that rref form stays
+        assert_eq!(format!("{}", matrix_rref.to_rref()), "1 0\n0 1");
+    }
+
+    #[test]
+    fn test_rref_larger_matrix() {
+        // Construct a 3x4 matrix
+        //  1 2 3 4
+        //  0 1 2 3
+        //  1 1 1 1
+        let rows = vec![
+            vec![
+                GFElement::new(1, 5),
+                GFElement::new(2, 5),
+                GFElement::new(3, 5),
+                GFElement::new(4, 5),
+            ],
+            vec![
+                GFElement::new(0, 5),
+                GFElement::new(1, 5),
+                GFElement::new(2, 5),
+                GFElement::new(3, 5),
+            ],
+            vec![
+                GFElement::new(1, 5),
+                GFElement::new(1, 5),
+                GFElement::new(1, 5),
+                GFElement::new(1, 5),
+            ],
+        ];
+        let matrix = Matrix::new(rows);
+        let matrix_rref = matrix.to_rref();
+
+        // Expected RREF:
+        //  1 0 4 3
+        //  0 1 2 3
+        //  0 0 0 0
+        assert_eq!(format!("{}", matrix_rref), "1 0 4 3\n0 1 2 3\n0 0 0 0");
+        // Verify that rref form stays
+        assert_eq!(
+            format!("{}", matrix_rref.to_rref()),
+            format!("{}", matrix_rref)
+        );
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
+    fn test_solvable() {
+        // Test whether a matrix is marked as solvable
+        let rows = vec![
+            vec![
+                GFElement::new(1, 2),
                 GFElement::new(0, 2),
                 GFElement::new(1, 2),
             ],
@@ -324,85 +1652,1112 @@ mod tests {
     }
 
     #[test]
-    fn test_unsolvable() {
-        // Test whether a matrix is marked as unsolvable
+    #[allow(clippy::bool_assert_comparison)]
+    fn test_unsolvable() {
+        // Test whether a matrix is marked as unsolvable
+        let rows = vec![
+            vec![
+                GFElement::new(1, 2),
+                GFElement::new(0, 2),
+                GFElement::new(1, 2),
+            ],
+            vec![
+                GFElement::new(0, 2),
+                GFElement::new(1, 2),
+                GFElement::new(1, 2),
+            ],
+            vec![
+                GFElement::new(0, 2),
+                GFElement::new(0, 2),
+                // Last "light" is 1 but no switches influence it
+                GFElement::new(1, 2),
+            ],
+        ];
+        let matrix = Matrix::new(rows);
+        assert_eq!(matrix.to_rref().is_solvable(), false);
+    }
+
+    #[test]
+    fn test_inconsistent_rows() {
+        // x = 1 and x = 0 contradict each other, 0 = 1 is a contradiction itself.
+        // Reduction moves y = 1 up to the second row.
+        let matrix = matrix_from_values(&[&[1, 0, 1], &[1, 0, 0], &[0, 0, 1], &[0, 1, 1]], 2);
+        assert_eq!(matrix.inconsistent_rows(), vec![2, 3]);
+        assert_eq!(matrix.solve_rref().inconsistent_rows(), vec![2, 3]);
+
+        let consistent = matrix_from_values(&[&[1, 0, 1], &[0, 1, 1]], 2);
+        assert!(consistent.inconsistent_rows().is_empty());
+    }
+
+    #[rstest]
+    #[case(vec![vec![GFElement::new(0,2),GFElement::new(0,2),GFElement::new(1,2)]], true)]
+    #[case(vec![vec![GFElement::new(0,2),GFElement::new(0,2),GFElement::new(0,2)]], false)]
+    #[case(vec![vec![GFElement::new(1,2),GFElement::new(0,2),GFElement::new(1,2)]], false)]
+    fn test_any_row_unsolvable(#[case] rows: Vec<Vec<GFElement>>, #[case] expected: bool) {
+        assert_eq!(
+            Matrix::new(rows).to_rref().is_any_row_unsolvable(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(vec![vec![GFElement::new(0,2),GFElement::new(0,2)]], true)]
+    #[case(vec![vec![GFElement::new(0,2),GFElement::new(1,2)]], true)]
+    #[case(vec![vec![GFElement::new(2,3),GFElement::new(0,3)]], false)]
+    #[case(vec![vec![GFElement::new(0,3),GFElement::new(2,3)]], false)]
+    #[case(vec![
+        vec![GFElement::new(0,3),GFElement::new(1,3)],
+        vec![GFElement::new(0,3),GFElement::new(2,3)]
+        ], false)]
+    fn test_every_row_has_a_pivot(#[case] rows: Vec<Vec<GFElement>>, #[case] expected: bool) {
+        assert_eq!(Matrix::new(rows).every_row_has_a_pivot(), expected);
+    }
+
+    #[rstest]
+    #[case(vec![
+        vec![GFElement::new(1,2),GFElement::new(0,2)],
+        vec![GFElement::new(0,2),GFElement::new(1,2)],
+        ], true)]
+    #[case(vec![
+        vec![GFElement::new(1,2),GFElement::new(0,2)],
+        vec![GFElement::new(0,2),GFElement::new(0,2)],
+        ], true)]
+    #[case(vec![
+        vec![GFElement::new(1,2),GFElement::new(1,2)],
+        vec![GFElement::new(0,2),GFElement::new(0,2)],
+        ], true)]
+    #[case(vec![
+        vec![GFElement::new(0,2),GFElement::new(0,2)],
+        vec![GFElement::new(1,2),GFElement::new(1,2)],
+        ], true)]
+    fn test_every_column_has_a_pivot(#[case] rows: Vec<Vec<GFElement>>, #[case] expected: bool) {
+        assert_eq!(Matrix::new(rows).every_column_has_a_pivot(), expected);
+    }
+
+    #[rstest]
+    #[case(vec![
+        vec![GFElement::new(1,2), GFElement::new(0,2), GFElement::new(1,2)],
+        vec![GFElement::new(0,2), GFElement::new(1,2), GFElement::new(1,2)],
+    ], vec![GFElement::new(1,2),GFElement::new(1,2)])]
+    #[case(vec![
+        vec![GFElement::new(2,7), GFElement::new(3,7), GFElement::new(5,7)],
+        vec![GFElement::new(2,7), GFElement::new(6,7), GFElement::new(1,7)],
+    ], vec![GFElement::new(1,7),GFElement::new(1,7)])]
+    #[case::free_variable(vec![
+        vec![GFElement::new(0,2), GFElement::new(1,2), GFElement::new(1,2)],
+        vec![GFElement::new(0,2), GFElement::new(0,2), GFElement::new(0,2)],
+    ], vec![GFElement::new(0,2),GFElement::new(1,2)])]
+    fn test_solution(#[case] rows: Vec<Vec<GFElement>>, #[case] expected: Vec<GFElement>) {
+        assert_eq!(Matrix::new(rows).solution(), Some(expected));
+    }
+
+    #[rstest]
+    // y = 1 with x free: the augmentation of the first row belongs to y
+    #[case::free_variable(vec![vec![0, 1, 1], vec![0, 0, 0]])]
+    // One equation in three unknowns, the last column has a single entry
+    #[case::wide(vec![vec![1, 2, 1, 2]])]
+    fn test_solution_is_not_last_column(#[case] values: Vec<Vec<i32>>) {
+        // solution() used to return the last column of the RREF form, which only
+        // matches the variables when every row pivots on the column of its own
+        // index. The solution read off the pivot columns does satisfy the system.
+        let matrix = matrix_from_values(&values, 3);
+        let satisfies = |x: &[GFElement]| {
+            x.len() == matrix.n_cols() - 1
+                && matrix
+                    .rows
+                    .iter()
+                    .all(|row| dot(&row[..x.len()], x) == *row.last().unwrap())
+        };
+        assert!(satisfies(&matrix.solution().unwrap()));
+        assert!(!satisfies(&matrix.to_rref().last_column()));
+    }
+
+    #[test]
+    fn test_concat_vertical() {
+        let top = matrix_from_values(&[&[0, 1, 2], &[1, 2, 0]], 3);
+        let bottom = matrix_from_values(&[&[2, 2, 2], &[1, 0, 1]], 3);
+        let stacked = top.concat_vertical(&bottom).unwrap();
+        assert_eq!((stacked.n_rows(), stacked.n_cols()), (4, 3));
+        assert_eq!(format!("{}", stacked), "0 1 2\n1 2 0\n2 2 2\n1 0 1");
+    }
+
+    #[test]
+    fn test_concat_horizontal() {
+        let left = matrix_from_values(&[&[0, 1], &[1, 2]], 3);
+        let right = matrix_from_values(&[&[2, 2], &[1, 0]], 3);
+        let joined = left.concat_horizontal(&right).unwrap();
+        assert_eq!((joined.n_rows(), joined.n_cols()), (2, 4));
+        assert_eq!(format!("{}", joined), "0 1 2 2\n1 2 1 0");
+    }
+
+    #[test]
+    fn test_concat_mismatch() {
+        let a = matrix_from_values(&[&[0, 1], &[1, 2]], 3);
+        let b = matrix_from_values(&[&[0, 1, 2]], 3);
+        let c = matrix_from_values(&[&[0, 1], &[1, 2]], 5);
+        assert_eq!(
+            a.concat_vertical(&b).err(),
+            Some(LinAlgError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            a.concat_horizontal(&b).err(),
+            Some(LinAlgError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            a.concat_vertical(&c).err(),
+            Some(LinAlgError::ModulusMismatch {
+                expected: 3,
+                found: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_rref_is_canonical() {
+        // The same rows in a different order reduce to the identical matrix
+        let rows = [
+            &[0, 2, 1, 4][..],
+            &[3, 1, 0, 2],
+            &[3, 3, 1, 1],
+            &[0, 0, 0, 0],
+        ];
+        let matrix = matrix_from_values(&rows, 5);
+        let permuted = matrix_from_values(&[rows[3], rows[2], rows[0], rows[1]], 5);
+
+        assert_eq!(
+            format!("{}", matrix.to_rref()),
+            format!("{}", permuted.to_rref())
+        );
+        // Zero rows are moved to the bottom
+        assert_eq!(
+            format!("{}", permuted.to_rref()),
+            "1 0 4 0\n0 1 3 2\n0 0 0 0\n0 0 0 0"
+        );
+    }
+
+    #[test]
+    fn test_null_space() {
+        // x0 + x2 = 1, x1 + 2 * x2 = 0 over GF(3) has the kernel spanned by (2, 1, 1)
+        let matrix = matrix_from_values(&[&[1, 0, 1, 1], &[0, 1, 2, 0]], 3);
+        assert_eq!(
+            matrix.null_space(),
+            vec![vec![
+                GFElement::new(2, 3),
+                GFElement::new(1, 3),
+                GFElement::new(1, 3)
+            ]]
+        );
+    }
+
+    #[rstest]
+    // x + y + z = 2
+    #[case(vec![vec![1, 1, 1, 2]], vec![0, 0, 2])]
+    // x + 2 y = 1, z + w = 2
+    #[case(vec![vec![1, 2, 0, 0, 1], vec![0, 0, 1, 1, 2]], vec![0, 2, 0, 2])]
+    #[case(vec![vec![0, 1, 2, 1], vec![1, 1, 0, 2], vec![1, 2, 2, 0]], vec![0, 2, 1])]
+    fn test_canonical_solution(#[case] values: Vec<Vec<i32>>, #[case] expected: Vec<i32>) {
+        let matrix = matrix_from_values(&values, 3);
+        let canonical = matrix.canonical_solution().unwrap();
+        assert_eq!(canonical.iter().map(|x| x.value).collect_vec(), expected);
+
+        // Compare against the smallest of all solutions
+        let n_vars = matrix.n_cols() - 1;
+        let smallest = (0..n_vars)
+            .map(|_| 0..3)
+            .multi_cartesian_product()
+            .find(|candidate| {
+                values.iter().all(|row| {
+                    let sum = candidate
+                        .iter()
+                        .zip(row.iter())
+                        .map(|(x, a)| x * a)
+                        .sum::<i32>();
+                    (sum - row[n_vars]).rem_euclid(3) == 0
+                })
+            })
+            .unwrap();
+        assert_eq!(smallest, expected);
+    }
+
+    #[test]
+    fn test_canonical_solution_unsolvable() {
+        let matrix = matrix_from_values(&[&[1, 1, 1], &[1, 1, 2]], 3);
+        assert_eq!(matrix.canonical_solution(), None);
+    }
+
+    #[test]
+    fn test_minimum_solution() {
+        // x0 + x2 = 1, x1 + x2 = 1 over GF(2) is solved by (1, 1, 0) and (0, 0, 1)
+        let matrix = matrix_from_values(&[&[1, 0, 1, 1], &[0, 1, 1, 1]], 2);
+        assert_eq!(
+            matrix.solution(),
+            Some(vec![
+                GFElement::new(1, 2),
+                GFElement::new(1, 2),
+                GFElement::new(0, 2)
+            ])
+        );
+        assert_eq!(
+            matrix.minimum_solution(),
+            Some(vec![
+                GFElement::new(0, 2),
+                GFElement::new(0, 2),
+                GFElement::new(1, 2)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_solution_bools() {
+        let matrix = Matrix::random(6, 7, 2, 5);
+        assert_eq!(
+            matrix.solution_bools(),
+            matrix
+                .solution()
+                .map(|solution| solution.iter().map(|x| x.value != 0).collect_vec())
+        );
+        let unsolvable = matrix_from_values(&[&[1, 1, 0], &[1, 1, 1]], 2);
+        assert_eq!(unsolvable.solution_bools(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Boolean solutions need a GF(2) matrix")]
+    fn test_solution_bools_wrong_modulus() {
+        matrix_from_values(&[&[1, 2]], 3).solution_bools();
+    }
+
+    #[test]
+    fn test_minimum_weighted_solution() {
+        let matrix = matrix_from_values(
+            &[
+                &[1, 1, 0, 1, 0, 0, 1],
+                &[0, 1, 1, 0, 1, 0, 0],
+                &[1, 0, 0, 1, 1, 1, 1],
+            ],
+            2,
+        );
+        let (best, cost) = matrix.minimum_weighted_solution(&[1; 6]).unwrap();
+        assert_eq!(Some(best.clone()), matrix.minimum_solution());
+        assert_eq!(cost, best.iter().filter(|x| x.value != 0).count() as u32);
+
+        // x0 + x1 = 1: with equal costs x1 is chosen by the tie-break, a cheaper
+        // x0 wins otherwise
+        let matrix = matrix_from_values(&[&[1, 1, 1]], 2);
+        let one_hot = |idx: usize| {
+            (0..2)
+                .map(|col_idx| GFElement::new((col_idx == idx) as i32, 2))
+                .collect_vec()
+        };
+        assert_eq!(
+            matrix.minimum_weighted_solution(&[3, 3]),
+            Some((one_hot(1), 3))
+        );
+        assert_eq!(
+            matrix.minimum_weighted_solution(&[1, 5]),
+            Some((one_hot(0), 1))
+        );
+    }
+
+    #[test]
+    fn test_minimum_solution_bounded() {
+        // x0 + x1 + x2 + x3 = 1 over GF(2) has 8 solutions, the particular
+        // solution x0 = 1 comes first and has weight 1 already
+        let matrix = matrix_from_values(&[&[1, 1, 1, 1, 1]], 2);
+        let (best, exhaustive) = matrix.minimum_solution_bounded(3).unwrap();
+        assert!(!exhaustive);
+        assert_eq!(best.iter().filter(|x| x.value != 0).count(), 1);
+
+        let (best, exhaustive) = matrix.minimum_solution_bounded(8).unwrap();
+        assert!(exhaustive);
+        assert_eq!(Some(best), matrix.minimum_solution());
+
+        let unsolvable = matrix_from_values(&[&[1, 1, 0], &[1, 1, 1]], 2);
+        assert_eq!(unsolvable.minimum_solution_bounded(10), None);
+    }
+
+    #[test]
+    fn test_minimum_solution_unique() {
+        // Without free variables the minimum solution is the solution
+        let matrix = matrix_from_values(&[&[1, 0, 1], &[0, 1, 1]], 2);
+        assert_eq!(matrix.minimum_solution(), matrix.solution());
+    }
+
+    #[test]
+    fn test_parse() {
+        let matrix = Matrix::parse("1 2 3\n\n -1 0 7\n", 5).unwrap();
+        assert_eq!(matrix, matrix_from_values(&[&[1, 2, 3], &[4, 0, 2]], 5));
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let reader = Cursor::new("1 2 3\n\n -1 0 7\n4 4 4\n");
+        let matrix = Matrix::from_reader(reader, 5).unwrap();
+        assert_eq!(
+            matrix,
+            matrix_from_values(&[&[1, 2, 3], &[4, 0, 2], &[4, 4, 4]], 5)
+        );
+    }
+
+    #[test]
+    fn test_from_reader_line_numbers() {
+        // The blank line counts, the invalid number is on line 2
+        let reader = Cursor::new("1 2\n\n3 x\n");
+        assert_eq!(
+            Matrix::from_reader(reader, 5),
+            Err(ParseError::InvalidNumber {
+                row: 2,
+                col: 1,
+                found: "x".to_string()
+            })
+        );
+        let reader = Cursor::new(vec![b'1', b' ', 0xff, b'\n']);
+        assert!(matches!(
+            Matrix::from_reader(reader, 5),
+            Err(ParseError::Io(_))
+        ));
+    }
+
+    #[rstest]
+    #[case::empty("\n  \n", 5, ParseError::Empty)]
+    #[case::ragged("1 2\n3", 5, ParseError::RaggedRow { row: 1, expected: 2, found: 1 })]
+    #[case::invalid_number("1 2\n3 x", 5, ParseError::InvalidNumber {
+        row: 1, col: 1, found: "x".to_string()
+    })]
+    #[case::invalid_modulus("1 2", 1, ParseError::InvalidModulus(GFError::InvalidModulus(1)))]
+    fn test_parse_invalid(#[case] s: &str, #[case] modulus: i32, #[case] expected: ParseError) {
+        assert_eq!(Matrix::parse(s, modulus), Err(expected));
+    }
+
+    #[test]
+    fn test_multiply() {
+        let a = matrix_from_values(&[&[1, 2], &[0, 1], &[2, 2]], 3);
+        let b = matrix_from_values(&[&[1, 0, 2], &[1, 1, 1]], 3);
+        assert_eq!(
+            a.multiply(&b),
+            Ok(matrix_from_values(&[&[0, 2, 1], &[1, 1, 1], &[1, 2, 0]], 3))
+        );
+        assert_eq!(
+            a.multiply(&a),
+            Err(LinAlgError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            b.multiply(&matrix_from_values(&[&[1], &[1], &[1]], 5)),
+            Err(LinAlgError::ModulusMismatch {
+                expected: 3,
+                found: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_solve_for() {
+        let matrix = matrix_from_values(&[&[2, 3], &[2, 6]], 7);
+        let rhs = vec![GFElement::new(5, 7), GFElement::new(1, 7)];
+        assert_eq!(
+            matrix.solve_for(&rhs),
+            Ok(Some(vec![GFElement::new(1, 7), GFElement::new(1, 7)]))
+        );
+        assert_eq!(
+            matrix.solve_for(&rhs[..1]),
+            Err(LinAlgError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            matrix.solve_for(&[GFElement::new(5, 7), GFElement::new(1, 5)]),
+            Err(LinAlgError::ModulusMismatch {
+                expected: 7,
+                found: 5
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::square(5, 5, 7, 1)]
+    #[case::wide(3, 6, 5, 2)]
+    #[case::tall(6, 3, 3, 3)]
+    #[case::gf2(8, 8, 2, 4)]
+    fn test_decompose(
+        #[case] n_rows: usize,
+        #[case] n_cols: usize,
+        #[case] modulus: i32,
+        #[case] seed: u64,
+    ) {
+        let matrix = Matrix::random(n_rows, n_cols, modulus, seed);
+        let decomposition = matrix.decompose().unwrap();
+        for rhs_seed in 0..8 {
+            let rhs = Matrix::random(n_rows, 1, modulus, rhs_seed + 100).last_column();
+            assert_eq!(Ok(decomposition.solve(&rhs)), matrix.solve_for(&rhs));
+        }
+        // A right hand side in the column space always has a solution
+        let x = Matrix::random(n_cols, 1, modulus, seed + 50);
+        let rhs = matrix.multiply(&x).unwrap().last_column();
+        assert!(decomposition.solve(&rhs).is_some());
+    }
+
+    #[test]
+    fn test_decompose_singular() {
+        // The third row is the sum of the first two, only right hand sides with
+        // the same relation can be solved
+        let matrix = matrix_from_values(&[&[0, 1, 2], &[1, 1, 0], &[1, 2, 2]], 3);
+        let decomposition = matrix.decompose().unwrap();
+        for rhs in [[1, 2, 0], [1, 1, 1], [0, 2, 2], [2, 2, 2]] {
+            let rhs = rhs.map(|value| GFElement::new(value, 3));
+            assert_eq!(Ok(decomposition.solve(&rhs)), matrix.solve_for(&rhs));
+        }
+        assert!(
+            decomposition
+                .solve(&[1, 1, 1].map(|v| GFElement::new(v, 3)))
+                .is_none()
+        );
+        assert!(
+            decomposition
+                .solve(&[1, 2, 0].map(|v| GFElement::new(v, 3)))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_solve_for_no_solution() {
+        let matrix = matrix_from_values(&[&[1, 1], &[1, 1]], 2);
+        let rhs = vec![GFElement::new(0, 2), GFElement::new(1, 2)];
+        assert_eq!(matrix.solve_for(&rhs), Ok(None));
+    }
+
+    #[rstest]
+    #[case(3, 4, 2)]
+    #[case(5, 5, 7)]
+    #[case(1, 6, 13)]
+    fn test_random(#[case] n_rows: usize, #[case] n_cols: usize, #[case] modulus: i32) {
+        let matrix = Matrix::random(n_rows, n_cols, modulus, 42);
+        assert_eq!((matrix.n_rows(), matrix.n_cols()), (n_rows, n_cols));
+        assert!(matrix.rows.iter().all(|row| row.len() == n_cols));
+        assert!(
+            matrix
+                .rows
+                .iter()
+                .flatten()
+                .all(|x| x.modulus == modulus && (0..modulus).contains(&x.value))
+        );
+        // The same seed reproduces the matrix
+        assert_eq!(matrix, Matrix::random(n_rows, n_cols, modulus, 42));
+    }
+
+    #[rstest]
+    #[case::rref(&[&[1, 0, 4, 0], &[0, 1, 3, 2], &[0, 0, 0, 0]], true)]
+    #[case::identity(&[&[1, 0], &[0, 1]], true)]
+    #[case::zero(&[&[0, 0], &[0, 0]], true)]
+    #[case::rows_swapped(&[&[0, 1, 3, 2], &[1, 0, 4, 0], &[0, 0, 0, 0]], false)]
+    #[case::row_scaled(&[&[2, 0, 3, 0], &[0, 1, 3, 2], &[0, 0, 0, 0]], false)]
+    #[case::row_added(&[&[1, 1, 2, 2], &[0, 1, 3, 2], &[0, 0, 0, 0]], false)]
+    #[case::zero_row_not_last(&[&[1, 0, 4, 0], &[0, 0, 0, 0], &[0, 1, 3, 2]], false)]
+    fn test_is_rref<R: AsRef<[i32]>>(#[case] values: &[R], #[case] expected: bool) {
+        assert_eq!(matrix_from_values(values, 5).is_rref(), expected);
+    }
+
+    #[test]
+    fn test_to_rref_is_rref() {
+        let matrix = Matrix::random(6, 7, 5, 1);
+        assert!(!matrix.is_rref());
+        assert!(matrix.to_rref().is_rref());
+    }
+
+    #[test]
+    fn test_trace() {
+        let matrix = matrix_from_values(&[&[1, 2, 0], &[4, 3, 1], &[0, 0, 4]], 5);
+        assert_eq!(matrix.trace(), Ok(GFElement::new(3, 5)));
+        assert_eq!(
+            matrix_from_values(&[&[1, 2, 0], &[4, 3, 1]], 5).trace(),
+            Err(LinAlgError::NotSquare {
+                n_rows: 2,
+                n_cols: 3
+            })
+        );
+        assert_eq!(Matrix::default().trace(), Err(LinAlgError::Empty));
+    }
+
+    #[rstest]
+    #[case::symmetric(&[&[1, 2], &[2, 0]], true)]
+    #[case::not_symmetric(&[&[1, 2], &[3, 0]], false)]
+    #[case::not_square(&[&[1, 2, 0], &[2, 0, 1]], false)]
+    fn test_is_symmetric<R: AsRef<[i32]>>(#[case] values: &[R], #[case] expected: bool) {
+        assert_eq!(matrix_from_values(values, 5).is_symmetric(), expected);
+    }
+
+    #[rstest]
+    #[case::toggle(&[&[1, 1, 0, 1], &[1, 1, 1, 0], &[0, 1, 1, 1]], true)]
+    #[case::not_symmetric(&[&[1, 1, 0, 1], &[0, 1, 1, 0], &[0, 1, 1, 1]], false)]
+    #[case::no_self_toggle(&[&[1, 1, 0, 1], &[1, 0, 1, 0], &[0, 1, 1, 1]], false)]
+    #[case::not_square(&[&[1, 1, 0], &[1, 1, 1], &[0, 1, 1]], false)]
+    fn test_is_toggle_matrix<R: AsRef<[i32]>>(#[case] values: &[R], #[case] expected: bool) {
+        assert_eq!(matrix_from_values(values, 2).is_toggle_matrix(), expected);
+    }
+
+    #[rstest]
+    #[case::one_free(&[&[1, 1, 0, 1], &[0, 0, 1, 0], &[1, 1, 1, 1]], vec![0, 2], vec![1])]
+    #[case::unique(&[&[1, 0, 0, 1], &[0, 1, 0, 0], &[1, 1, 1, 1]], vec![0, 1, 2], vec![])]
+    #[case::inconsistent(&[&[0, 1, 1, 1], &[0, 1, 1, 0], &[0, 0, 0, 1]], vec![1], vec![0, 2])]
+    fn test_pivot_and_free_columns<R: AsRef<[i32]>>(
+        #[case] values: &[R],
+        #[case] pivots: Vec<usize>,
+        #[case] free: Vec<usize>,
+    ) {
+        let matrix = matrix_from_values(values, 2);
+        assert_eq!(matrix.pivot_columns(), pivots);
+        assert_eq!(matrix.free_columns(), free);
+    }
+
+    #[test]
+    fn test_submatrix() {
+        let matrix = matrix_from_values(&[&[1, 2, 3], &[4, 5, 6], &[7, 8, 9]], 11);
+        assert_eq!(
+            matrix.submatrix(0..2, 0..2),
+            Ok(matrix_from_values(&[&[1, 2], &[4, 5]], 11))
+        );
+        assert_eq!(
+            matrix.submatrix(1..3, 2..3),
+            Ok(matrix_from_values(&[&[6], &[9]], 11))
+        );
+    }
+
+    #[test]
+    fn test_to_latex() {
+        let matrix = matrix_from_values(&[&[1, 1, 0], &[0, 1, 1]], 2);
+        assert_eq!(
+            matrix.to_latex(),
+            "\\begin{pmatrix}\n1 & 1 & 0 \\\\\n0 & 1 & 1\n\\end{pmatrix}"
+        );
+        assert_eq!(
+            matrix.to_latex_augmented(),
+            "\\left(\\begin{array}{cc|c}\n1 & 1 & 0 \\\\\n0 & 1 & 1\n\\end{array}\\right)"
+        );
+    }
+
+    #[rstest]
+    #[case::duplicate(&[&[1, 2, 3], &[4, 5, 6], &[1, 2, 3]], vec![2])]
+    #[case::combination(&[&[1, 0, 1], &[0, 1, 1], &[1, 1, 2], &[0, 0, 1]], vec![2])]
+    #[case::zero_row(&[&[0, 0, 0], &[1, 1, 1]], vec![0])]
+    #[case::independent(&[&[1, 0, 0], &[0, 1, 0], &[0, 0, 1]], vec![])]
+    fn test_dependent_rows<R: AsRef<[i32]>>(#[case] values: &[R], #[case] expected: Vec<usize>) {
+        assert_eq!(matrix_from_values(values, 7).dependent_rows(), expected);
+    }
+
+    #[test]
+    fn test_is_row_equivalent() {
+        let matrix = Matrix::random(4, 5, 7, 11);
+        assert!(matrix.is_row_equivalent(&matrix.to_rref()));
+        assert!(matrix.to_rref().is_row_equivalent(&matrix));
+
+        let a = matrix_from_values(&[&[1, 2], &[3, 4]], 7);
+        let b = matrix_from_values(&[&[1, 2], &[2, 4]], 7);
+        assert!(!a.is_row_equivalent(&b));
+        // Shape and modulus have to match
+        assert!(!a.is_row_equivalent(&a.pad(3, 2).unwrap()));
+        assert!(!a.is_row_equivalent(&matrix_from_values(&[&[1, 2], &[3, 4]], 5)));
+    }
+
+    #[test]
+    fn test_swap_columns() {
+        let mut matrix = matrix_from_values(&[&[1, 2, 3], &[4, 5, 6]], 7);
+        matrix.swap_columns(0, 2);
+        assert_eq!(matrix, matrix_from_values(&[&[3, 2, 1], &[6, 5, 4]], 7));
+        matrix.swap_columns(1, 1);
+        assert_eq!(matrix, matrix_from_values(&[&[3, 2, 1], &[6, 5, 4]], 7));
+    }
+
+    #[test]
+    fn test_pad() {
+        let matrix = matrix_from_values(&[&[1, 2], &[3, 4]], 5);
+        let padded = matrix.pad(3, 4).unwrap();
+        assert_eq!(format!("{}", padded), "1 2 0 0\n3 4 0 0\n0 0 0 0");
+        assert_eq!(padded.modulus(), Some(5));
+        assert_eq!(matrix.pad(2, 2), Ok(matrix.clone()));
+        assert_eq!(
+            matrix.pad(1, 4),
+            Err(LinAlgError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            matrix.pad(3, 1),
+            Err(LinAlgError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(Matrix::default().pad(2, 2), Err(LinAlgError::Empty));
+    }
+
+    #[test]
+    fn test_submatrix_out_of_bounds() {
+        let matrix = matrix_from_values(&[&[1, 2, 3], &[4, 5, 6], &[7, 8, 9]], 11);
+        assert_eq!(
+            matrix.submatrix(0..2, 1..4),
+            Err(LinAlgError::OutOfBounds {
+                range: 1..4,
+                len: 3
+            })
+        );
+        assert_eq!(
+            matrix.submatrix(2..4, 0..2),
+            Err(LinAlgError::OutOfBounds {
+                range: 2..4,
+                len: 3
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Matrix has mixed moduli: row 1 col 0 has modulus 3, expected 5")]
+    fn test_to_rref_mixed_moduli() {
+        let matrix = Matrix::new(vec![
+            vec![GFElement::new(1, 5), GFElement::new(2, 5)],
+            vec![GFElement::new(1, 3), GFElement::new(2, 5)],
+        ]);
+        matrix.to_rref();
+    }
+
+    #[test]
+    fn test_rows_and_into_rows() {
         let rows = vec![
-            vec![
-                GFElement::new(1, 2),
-                GFElement::new(0, 2),
-                GFElement::new(1, 2),
-            ],
-            vec![
-                GFElement::new(0, 2),
-                GFElement::new(1, 2),
-                GFElement::new(1, 2),
-            ],
-            vec![
-                GFElement::new(0, 2),
-                GFElement::new(0, 2),
-                // Last "light" is 1 but no switches influence it
-                GFElement::new(1, 2),
-            ],
+            vec![GFElement::new(1, 5), GFElement::new(2, 5)],
+            vec![GFElement::new(3, 5), GFElement::new(4, 5)],
         ];
-        let matrix = Matrix::new(rows);
-        assert_eq!(matrix.to_rref().is_solvable(), false);
+        let matrix = Matrix::new(rows.clone());
+        assert_eq!(matrix.rows(), rows.as_slice());
+        assert_eq!(matrix.into_rows(), rows);
+    }
+
+    #[test]
+    fn test_last_column_and_coefficient_columns() {
+        let matrix = matrix_from_values(&[&[1, 2, 3], &[4, 5, 6]], 7);
+        assert_eq!(
+            matrix.last_column(),
+            vec![GFElement::new(3, 7), GFElement::new(6, 7)]
+        );
+        assert_eq!(
+            matrix.coefficient_columns(),
+            matrix_from_values(&[&[1, 2], &[4, 5]], 7)
+        );
     }
 
     #[rstest]
-    #[case(vec![vec![GFElement::new(0,2),GFElement::new(0,2),GFElement::new(1,2)]], true)]
-    #[case(vec![vec![GFElement::new(0,2),GFElement::new(0,2),GFElement::new(0,2)]], false)]
-    #[case(vec![vec![GFElement::new(1,2),GFElement::new(0,2),GFElement::new(1,2)]], false)]
-    fn test_any_row_unsolvable(#[case] rows: Vec<Vec<GFElement>>, #[case] expected: bool) {
+    #[case(4, 4, 2, 1)]
+    #[case(3, 5, 7, 2)]
+    #[case(5, 3, 5, 3)]
+    fn test_rref_steps(
+        #[case] n_rows: usize,
+        #[case] n_cols: usize,
+        #[case] modulus: i32,
+        #[case] seed: u64,
+    ) {
+        let matrix = Matrix::random(n_rows, n_cols, modulus, seed);
+        let (rref, steps) = matrix.rref_steps();
+        assert_eq!(rref, matrix.to_rref());
+
+        let mut replayed = matrix.clone();
+        for step in steps.iter() {
+            replayed.apply_row_op(step);
+        }
+        assert_eq!(replayed, rref);
+    }
+
+    #[test]
+    fn test_rref_steps_recorded_ops() {
+        let matrix = matrix_from_values(&[&[0, 2], &[1, 1]], 3);
+        let (_, steps) = matrix.rref_steps();
         assert_eq!(
-            Matrix::new(rows).to_rref().is_any_row_unsolvable(),
-            expected
+            steps,
+            vec![
+                RowOp::Swap(0, 1),
+                RowOp::Scale(1, GFElement::new(2, 3)),
+                RowOp::AddScaled {
+                    target: 0,
+                    source: 1,
+                    factor: GFElement::new(2, 3)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_solve_rref() {
+        // The second row is inconsistent, to_rref turns the augmentation into a
+        // pivot column and clears the target of the first row
+        let matrix = matrix_from_values(&[&[1, 1, 1], &[1, 1, 0]], 2);
+        assert_eq!(
+            matrix.to_rref(),
+            matrix_from_values(&[&[1, 1, 0], &[0, 0, 1]], 2)
         );
+        assert_eq!(
+            matrix.solve_rref(),
+            matrix_from_values(&[&[1, 1, 1], &[0, 0, 1]], 2)
+        );
+        assert_eq!(matrix.solution(), None);
+
+        // Both agree once the augmentation holds no pivot
+        let matrix = matrix_from_values(&[&[1, 2, 3], &[3, 1, 6]], 7);
+        assert_eq!(matrix.solve_rref(), matrix.to_rref());
+    }
+
+    #[test]
+    fn test_to_rref_with_progress() {
+        let matrix = Matrix::random(6, 7, 5, 21);
+        let mut progress = vec![];
+        let rref = matrix.to_rref_with_progress(|done, total| progress.push((done, total)));
+        assert_eq!(rref, matrix.to_rref());
+        assert_eq!(progress.len(), rref.rank());
+        assert_eq!(progress.last(), Some(&(rref.rank(), 6)));
+    }
+
+    #[test]
+    fn test_to_rref_mut() {
+        let matrix = Matrix::random(5, 6, 7, 3);
+        let mut reduced = matrix.clone();
+        reduced.to_rref_mut();
+        assert_eq!(reduced, matrix.to_rref());
+        assert!(reduced.is_rref());
     }
 
     #[rstest]
-    #[case(vec![vec![GFElement::new(0,2),GFElement::new(0,2)]], true)]
-    #[case(vec![vec![GFElement::new(0,2),GFElement::new(1,2)]], true)]
-    #[case(vec![vec![GFElement::new(2,3),GFElement::new(0,3)]], false)]
-    #[case(vec![vec![GFElement::new(0,3),GFElement::new(2,3)]], false)]
-    #[case(vec![
-        vec![GFElement::new(0,3),GFElement::new(1,3)],
-        vec![GFElement::new(0,3),GFElement::new(2,3)]
-        ], false)]
-    fn test_every_row_has_a_pivot(#[case] rows: Vec<Vec<GFElement>>, #[case] expected: bool) {
-        assert_eq!(Matrix::new(rows).every_row_has_a_pivot(), expected);
+    #[case(2, 11)]
+    #[case(2, 12)]
+    #[case(5, 13)]
+    fn test_dot(#[case] modulus: i32, #[case] seed: u64) {
+        let vectors = Matrix::random(2, 17, modulus, seed);
+        let (a, b) = (&vectors.rows[0], &vectors.rows[1]);
+        assert_eq!(dot(a, b), dot_generic(a, b));
+        if modulus == 2 {
+            assert_eq!(dot_gf2(a, b), dot_generic(a, b));
+        }
     }
 
     #[rstest]
-    #[case(vec![
-        vec![GFElement::new(1,2),GFElement::new(0,2)],
-        vec![GFElement::new(0,2),GFElement::new(1,2)],
-        ], true)]
-    #[case(vec![
-        vec![GFElement::new(1,2),GFElement::new(0,2)],
-        vec![GFElement::new(0,2),GFElement::new(0,2)],
-        ], true)]
-    #[case(vec![
-        vec![GFElement::new(1,2),GFElement::new(1,2)],
-        vec![GFElement::new(0,2),GFElement::new(0,2)],
-        ], true)]
-    #[case(vec![
-        vec![GFElement::new(0,2),GFElement::new(0,2)],
-        vec![GFElement::new(1,2),GFElement::new(1,2)],
-        ], true)]
-    fn test_every_column_has_a_pivot(#[case] rows: Vec<Vec<GFElement>>, #[case] expected: bool) {
-        assert_eq!(Matrix::new(rows).every_column_has_a_pivot(), expected);
+    #[case(2, 11)]
+    #[case(2, 12)]
+    #[case(5, 13)]
+    fn test_xor_rows(#[case] modulus: i32, #[case] seed: u64) {
+        let vectors = Matrix::random(2, 17, modulus, seed);
+        let mut expected = vectors.rows[0].clone();
+        xor_rows_generic(&mut expected, &vectors.rows[1]);
+
+        let mut target = vectors.rows[0].clone();
+        xor_rows(&mut target, &vectors.rows[1]);
+        assert_eq!(target, expected);
+        if modulus == 2 {
+            let mut target = vectors.rows[0].clone();
+            xor_rows_gf2(&mut target, &vectors.rows[1]);
+            assert_eq!(target, expected);
+        }
     }
 
     #[rstest]
-    #[case(vec![
-        vec![GFElement::new(1,2), GFElement::new(0,2), GFElement::new(1,2)],
-        vec![GFElement::new(0,2), GFElement::new(1,2), GFElement::new(1,2)],
-    ], vec![GFElement::new(1,2),GFElement::new(1,2)])]
-    #[case(vec![
-        vec![GFElement::new(2,7), GFElement::new(3,7), GFElement::new(5,7)],
-        vec![GFElement::new(2,7), GFElement::new(6,7), GFElement::new(1,7)],
-    ], vec![GFElement::new(1,7),GFElement::new(1,7)])]
-    fn test_solution(#[case] rows: Vec<Vec<GFElement>>, #[case] expected: Vec<GFElement>) {
-        assert_eq!(Matrix::new(rows).solution(), Some(expected));
+    #[case::unique(&[&[1, 0, 1], &[0, 1, 1]], true)]
+    #[case::inconsistent(&[&[1, 1, 1], &[1, 1, 0]], false)]
+    #[case::zero_row_with_target(&[&[0, 0, 1]], false)]
+    fn test_is_consistent<R: AsRef<[i32]>>(#[case] values: &[R], #[case] expected: bool) {
+        assert_eq!(matrix_from_values(values, 2).is_consistent(), expected);
+    }
+
+    #[test]
+    fn test_consistent_but_not_unique() {
+        // x0 + x1 = 1 over GF(2) has the two solutions (1, 0) and (0, 1)
+        let matrix = matrix_from_values(&[&[1, 1, 1], &[1, 1, 1]], 2);
+        assert!(matrix.is_consistent());
+        assert!(!matrix.free_columns().is_empty());
+        assert!(matrix.is_solvable());
+    }
+
+    #[test]
+    fn test_parametric_solution() {
+        // x0 + x2 = 1, x1 + x2 = 1 over GF(2) with x2 as free variable
+        let matrix = matrix_from_values(&[&[1, 0, 1, 1], &[0, 1, 1, 1]], 2);
+        let solution = matrix.parametric_solution().unwrap();
+        assert_eq!(solution.free.len(), 1);
+        assert_eq!(format!("{}", solution), "x0 = 1 + x2\nx1 = 1 + x2\nx2 free");
+    }
+
+    #[test]
+    fn test_parametric_solution_coefficients() {
+        // x0 + x2 = 1, x1 + 2 * x2 = 0 over GF(3)
+        let matrix = matrix_from_values(&[&[1, 0, 1, 1], &[0, 1, 2, 0]], 3);
+        assert_eq!(
+            format!("{}", matrix.parametric_solution().unwrap()),
+            "x0 = 1 + 2*x2\nx1 = 0 + x2\nx2 free"
+        );
+        assert_eq!(
+            matrix_from_values(&[&[1, 1, 1], &[1, 1, 0]], 3).parametric_solution(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_kronecker() {
+        let a = matrix_from_values(&[&[1, 1], &[0, 1]], 2);
+        let b = matrix_from_values(&[&[1, 0], &[1, 1]], 2);
+        let product = a.kronecker(&b).unwrap();
+        assert_eq!((product.n_rows(), product.n_cols()), (4, 4));
+        assert_eq!(format!("{}", product), "1 0 1 0\n1 1 1 1\n0 0 1 0\n0 0 1 1");
+        // Entry (i * 2 + k, j * 2 + l) is a[i][j] * b[k][l]
+        assert_eq!(product.rows[1][2], a.rows[0][1] * b.rows[1][0]);
+        assert_eq!(product.rows[2][1], a.rows[1][0] * b.rows[0][1]);
+    }
+
+    #[test]
+    fn test_kronecker_shape_and_modulus() {
+        let a = Matrix::random(2, 3, 5, 1);
+        let b = Matrix::random(4, 1, 5, 2);
+        let product = a.kronecker(&b).unwrap();
+        assert_eq!((product.n_rows(), product.n_cols()), (8, 3));
+        assert_eq!(
+            a.kronecker(&Matrix::random(2, 2, 3, 1)),
+            Err(LinAlgError::ModulusMismatch {
+                expected: 5,
+                found: 3
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::square(4, 4, 1)]
+    #[case::wide(3, 5, 2)]
+    #[case::tall(5, 3, 3)]
+    fn test_rref_with_transform(#[case] n_rows: usize, #[case] n_cols: usize, #[case] seed: u64) {
+        let matrix = Matrix::random(n_rows, n_cols, 5, seed);
+        let (rref, transform) = matrix.rref_with_transform();
+        assert_eq!(rref, matrix.to_rref());
+        assert_eq!(transform.multiply(&matrix), Ok(rref));
+    }
+
+    fn verify_rref_invariants(matrix: &Matrix) -> bool {
+        // The RREF form is in RREF, has the rank of the transpose (computed by a
+        // separate reduction) and is row-equivalent to the matrix: an invertible
+        // transformation maps one onto the other
+        let (rref, transform) = matrix.rref_with_transform();
+        rref.is_rref()
+            && rref == matrix.to_rref()
+            && rref.rank() == matrix.transpose().rank()
+            && transform.rank() == matrix.n_rows()
+            && transform.multiply(matrix) == Ok(rref)
+    }
+
+    fn small_matrix() -> impl Strategy<Value = Matrix> {
+        // Matrices of up to 5x5 elements over a small prime field
+        (prop::sample::select(vec![2, 3, 5, 7]), 1..6usize, 1..6usize).prop_flat_map(
+            |(modulus, n_rows, n_cols)| {
+                prop::collection::vec(prop::collection::vec(0..modulus, n_cols), n_rows)
+                    .prop_map(move |values| matrix_from_values(&values, modulus))
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn test_rref_invariants(matrix in small_matrix()) {
+            prop_assert!(verify_rref_invariants(&matrix), "{}", matrix);
+        }
+    }
+
+    #[test]
+    fn test_rref_with_transform_rank_deficient() {
+        let matrix = matrix_from_values(&[&[1, 2, 3], &[2, 4, 1], &[3, 1, 4]], 5);
+        let (rref, transform) = matrix.rref_with_transform();
+        assert_eq!(format!("{}", rref), "1 2 3\n0 0 0\n0 0 0");
+        assert_eq!(transform.multiply(&matrix), Ok(rref));
+    }
+
+    #[test]
+    fn test_map() {
+        let matrix = matrix_from_values(&[&[0, 1, 2], &[3, 4, 0]], 5);
+        assert_eq!(
+            matrix.map(|element| -element),
+            matrix_from_values(&[&[0, 4, 3], &[2, 1, 0]], 5)
+        );
+        // Lift into another field
+        assert_eq!(
+            matrix.map(|element| GFElement::new(element.value, 7)),
+            matrix_from_values(&[&[0, 1, 2], &[3, 4, 0]], 7)
+        );
+    }
+
+    #[test]
+    fn test_with_modulus() {
+        let matrix = matrix_from_values(&[&[1, 0, 1], &[0, 1, 1]], 2);
+        let lifted = matrix.with_modulus(3);
+        assert_eq!(lifted.modulus(), Some(3));
+        assert_eq!(lifted, matrix_from_values(&[&[1, 0, 1], &[0, 1, 1]], 3));
+        // Values are reduced into the new field
+        assert_eq!(
+            matrix_from_values(&[&[4, 6]], 7).with_modulus(5),
+            matrix_from_values(&[&[4, 1]], 5)
+        );
+    }
+
+    #[rstest]
+    #[case::full(&[&[1, 2], &[3, 4]], 2)]
+    #[case::deficient(&[&[1, 2, 3], &[2, 4, 1], &[3, 1, 4]], 1)]
+    #[case::zero(&[&[0, 0], &[0, 0]], 0)]
+    fn test_rank<R: AsRef<[i32]>>(#[case] values: &[R], #[case] expected: usize) {
+        assert_eq!(matrix_from_values(values, 5).rank(), expected);
+    }
+
+    #[rstest]
+    #[case::unique(&[&[1, 0, 1], &[0, 1, 1]], 3, 1)]
+    #[case::one_free(&[&[1, 0, 1, 1], &[0, 1, 2, 0]], 3, 3)]
+    #[case::two_free(&[&[1, 1, 1, 1]], 3, 9)]
+    #[case::unsolvable(&[&[1, 1, 1], &[1, 1, 0]], 3, 0)]
+    fn test_solutions_iter<R: AsRef<[i32]>>(
+        #[case] values: &[R],
+        #[case] modulus: i32,
+        #[case] expected: u128,
+    ) {
+        let matrix = matrix_from_values(values, modulus);
+        assert_eq!(matrix.solution_count(), expected);
+        assert_eq!(matrix.solutions_iter().count() as u128, expected);
+        // Every solution is distinct and satisfies the system
+        let solutions = matrix.solutions_iter().collect_vec();
+        let distinct = solutions
+            .iter()
+            .map(|solution| solution.iter().map(|x| x.value).collect_vec())
+            .unique()
+            .count();
+        assert_eq!(distinct, solutions.len());
+        let coefficients = matrix
+            .submatrix(0..matrix.n_rows(), 0..matrix.n_cols() - 1)
+            .unwrap();
+        for solution in solutions {
+            let column = Matrix::new(solution.iter().map(|&x| vec![x]).collect_vec());
+            let target = matrix.submatrix(0..matrix.n_rows(), matrix.n_cols() - 1..matrix.n_cols());
+            assert_eq!(coefficients.multiply(&column), target);
+        }
+    }
+
+    #[test]
+    fn test_solutions_iter_next() {
+        let matrix = matrix_from_values(&[&[1, 0, 1, 1], &[0, 1, 1, 1]], 2);
+        let first = matrix.solutions_iter().next().unwrap();
+        assert_eq!(first, matrix.solution().unwrap());
+    }
+
+    #[test]
+    fn test_matrix_display_column_widths() {
+        // Only the column holding multi-digit values is padded
+        let matrix = matrix_from_values(&[&[1, 2, 3], &[10, 4, 5], &[6, 7, 8]], 11);
+        assert_eq!(format!("{}", matrix), " 1 2 3\n10 4 5\n 6 7 8");
+    }
+
+    #[test]
+    fn test_matrix_display_ragged() {
+        let matrix = Matrix::new(vec![
+            vec![GFElement::new(1, 11), GFElement::new(10, 11)],
+            vec![GFElement::new(10, 11)],
+        ]);
+        assert_eq!(format!("{}", matrix), " 1 10\n10");
+    }
+
+    #[test]
+    fn test_matrix_display_aligned_gf13() {
+        // A wide first column does not widen the other columns
+        let matrix = matrix_from_values(&[&[12, 1, 0], &[3, 4, 5], &[10, 7, 9]], 13);
+        assert_eq!(format!("{}", matrix), "12 1 0\n 3 4 5\n10 7 9");
+    }
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct Rational {
+        // A fraction in lowest terms with a positive denominator
+        num: i32,
+        den: i32,
+    }
+
+    impl Rational {
+        fn new(num: i32, den: i32) -> Self {
+            assert_ne!(den, 0, "Denominator should not be 0");
+            let divisor = gcd(num, den) * den.signum();
+            Rational {
+                num: num / divisor,
+                den: den / divisor,
+            }
+        }
+    }
+
+    impl Add for Rational {
+        type Output = Rational;
+
+        fn add(self, other: Rational) -> Rational {
+            Rational::new(
+                self.num * other.den + other.num * self.den,
+                self.den * other.den,
+            )
+        }
+    }
+
+    impl Sub for Rational {
+        type Output = Rational;
+
+        fn sub(self, other: Rational) -> Rational {
+            self + -other
+        }
+    }
+
+    impl Neg for Rational {
+        type Output = Rational;
+
+        fn neg(self) -> Rational {
+            Rational::new(-self.num, self.den)
+        }
+    }
+
+    impl Mul for Rational {
+        type Output = Rational;
+
+        fn mul(self, other: Rational) -> Rational {
+            Rational::new(self.num * other.num, self.den * other.den)
+        }
+    }
+
+    impl Div for Rational {
+        type Output = Rational;
+
+        fn div(self, other: Rational) -> Rational {
+            Rational::new(self.num * other.den, self.den * other.num)
+        }
+    }
+
+    impl FieldElement for Rational {
+        fn zero_like(&self) -> Self {
+            Rational::new(0, 1)
+        }
+
+        fn one_like(&self) -> Self {
+            Rational::new(1, 1)
+        }
+    }
+
+    #[test]
+    fn test_solution_over_rationals() {
+        // x + y = 1 and x - y = 1/3 have the solution x = 2/3, y = 1/3
+        let r = |num, den| Rational::new(num, den);
+        let matrix = Matrix::new(vec![
+            vec![r(1, 1), r(1, 1), r(1, 1)],
+            vec![r(1, 1), r(-1, 1), r(1, 3)],
+        ]);
+        assert_eq!(matrix.solution(), Some(vec![r(2, 3), r(1, 3)]));
+
+        let inconsistent = Matrix::new(vec![
+            vec![r(1, 2), r(1, 1), r(1, 1)],
+            vec![r(1, 1), r(2, 1), r(1, 1)],
+        ]);
+        assert_eq!(inconsistent.solution(), None);
     }
 }
@@ -0,0 +1,121 @@
+use lights_out_solver::puzzle::{Puzzle, board_analysis};
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: lights_out_solver [--modulus <p>] < board.txt
+       lights_out_solver analyze <width> <height>";
+
+fn parse_modulus(args: &[String]) -> Result<i32, String> {
+    // Read the optional --modulus flag, defaulting to the classic on/off puzzle
+    let mut modulus = 2;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--modulus" => args.next().ok_or("--modulus requires a value")?.as_str(),
+            _ => match arg.strip_prefix("--modulus=") {
+                Some(value) => value,
+                None => return Err(format!("Unknown argument '{}'", arg)),
+            },
+        };
+        modulus = value
+            .parse()
+            .map_err(|_| format!("Invalid modulus '{}'", value))?;
+        if modulus < 2 {
+            return Err(format!("Modulus must be at least 2, got {}", modulus));
+        }
+    }
+    Ok(modulus)
+}
+
+fn parse_dimensions(args: &[String]) -> Result<(usize, usize), String> {
+    // Read the width and height of the analyze subcommand
+    let [width, height] = args else {
+        return Err("analyze requires a width and a height".to_string());
+    };
+    let parse = |value: &String| match value.parse::<usize>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(format!("Invalid board size '{}'", value)),
+    };
+    Ok((parse(width)?, parse(height)?))
+}
+
+fn analyze(args: &[String]) -> ExitCode {
+    // Print the facts about the toggle matrix of a board size
+    let (width, height) = match parse_dimensions(args) {
+        Ok(dimensions) => dimensions,
+        Err(msg) => {
+            eprintln!("{}\n{}", msg, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let analysis = board_analysis(width, height);
+    let format_count = |count: Option<u128>| match count {
+        Some(count) => count.to_string(),
+        None => "too many to count".to_string(),
+    };
+    println!("rank: {}", analysis.rank);
+    println!("nullity: {}", analysis.nullity);
+    println!("quiet patterns: {}", format_count(analysis.quiet_patterns));
+    println!(
+        "solvable configurations: {}",
+        format_count(analysis.solvable_configurations)
+    );
+    ExitCode::SUCCESS
+}
+
+fn format_presses(puzzle: &Puzzle, presses: &[i32]) -> String {
+    // Draw the solution on the board: '.' for buttons not pressed, 'X' for
+    // buttons pressed once and the press count otherwise
+    presses
+        .chunks(puzzle.width)
+        .map(|row| {
+            row.iter()
+                .map(|&count| match count {
+                    0 => ".".to_string(),
+                    1 => "X".to_string(),
+                    _ => count.to_string(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().is_some_and(|arg| arg == "analyze") {
+        return analyze(&args[1..]);
+    }
+    let modulus = match parse_modulus(&args) {
+        Ok(modulus) => modulus,
+        Err(msg) => {
+            eprintln!("{}\n{}", msg, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut input = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut input) {
+        eprintln!("Failed to read board from stdin: {}", err);
+        return ExitCode::from(2);
+    }
+    let puzzle = match input.parse::<Puzzle>() {
+        Ok(puzzle) => puzzle.with_modulus(modulus),
+        Err(msg) => {
+            eprintln!("Invalid board: {}", msg);
+            return ExitCode::from(2);
+        }
+    };
+
+    match puzzle.minimum_presses() {
+        Some(presses) => {
+            let counts = presses.iter().map(|x| x.value).collect::<Vec<_>>();
+            println!("{}", format_presses(&puzzle, &counts));
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("The board cannot be solved");
+            ExitCode::FAILURE
+        }
+    }
+}
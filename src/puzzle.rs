@@ -0,0 +1,1869 @@
+use crate::error::ParseError;
+use crate::finite_field::GFElement;
+use crate::linalg::Matrix;
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Puzzle {
+    // A rectangular Lights Out board. Cells are stored row-major, true meaning
+    // the light is on. Pressing a button advances its own cell and the
+    // orthogonally adjacent cells by one state, lights cycle through modulus
+    // states (2 for the classic on/off puzzle).
+    pub width: usize,
+    pub height: usize,
+    pub initial: Vec<bool>,
+    pub modulus: i32,
+    // Buttons that cannot be pressed, their cells still toggle when a neighbor
+    // is pressed
+    pub locked: Vec<bool>,
+}
+
+impl Puzzle {
+    pub fn new(width: usize, height: usize, initial: Vec<bool>) -> Self {
+        assert_eq!(
+            initial.len(),
+            width * height,
+            "Board of {}x{} needs {} cells",
+            width,
+            height,
+            width * height
+        );
+        Puzzle {
+            width,
+            height,
+            locked: vec![false; initial.len()],
+            initial,
+            modulus: 2,
+        }
+    }
+
+    pub fn strip(n: usize, initial: &[bool]) -> Self {
+        // Create a 1xn board, pressing a button toggles its cell and the cells to
+        // its left and right
+        Puzzle::new(n, 1, initial.to_vec())
+    }
+
+    pub fn from_grid(grid: Vec<Vec<bool>>) -> Result<Self, ParseError> {
+        // Create a puzzle from a board given row by row, all rows need to have
+        // the same length and at least one cell
+        let width = grid.first().ok_or(ParseError::Empty)?.len();
+        if width == 0 {
+            return Err(ParseError::Empty);
+        }
+        if let Some((row_idx, row)) = grid.iter().enumerate().find(|(_, row)| row.len() != width) {
+            return Err(ParseError::RaggedRow {
+                row: row_idx,
+                expected: width,
+                found: row.len(),
+            });
+        }
+
+        let height = grid.len();
+        Ok(Puzzle::new(
+            width,
+            height,
+            grid.into_iter().flatten().collect_vec(),
+        ))
+    }
+
+    pub fn with_modulus(self, modulus: i32) -> Self {
+        // Play the puzzle with lights cycling through modulus states
+        Puzzle { modulus, ..self }
+    }
+
+    pub fn with_locked_buttons(&mut self, locked: &[bool]) {
+        // Mark the buttons that cannot be pressed. Their variables are removed
+        // from the system, which can make the board unsolvable, and solutions
+        // never press them.
+        assert_eq!(
+            locked.len(),
+            self.initial.len(),
+            "Locked buttons should have one entry per cell"
+        );
+        self.locked = locked.to_vec();
+    }
+
+    pub fn press(&mut self, row: usize, col: usize) {
+        // Press the button at (row, col), toggling its cell and the orthogonally
+        // adjacent cells of initial. Pressing outside of the board does nothing.
+        // Lights only have the states on and off, so the puzzle needs modulus 2.
+        assert_eq!(self.modulus, 2, "Pressing is only supported for modulus 2");
+        if row >= self.height || col >= self.width {
+            return;
+        }
+        let neighbors = [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)];
+        for (row_offset, col_offset) in neighbors {
+            let (Some(cell_row), Some(cell_col)) = (
+                row.checked_add_signed(row_offset),
+                col.checked_add_signed(col_offset),
+            ) else {
+                continue;
+            };
+            if cell_row < self.height && cell_col < self.width {
+                let cell = &mut self.initial[cell_row * self.width + cell_col];
+                *cell = !*cell;
+            }
+        }
+    }
+
+    pub fn toggle_matrix(width: usize, height: usize, modulus: i32) -> Matrix {
+        // Create the coefficient matrix of the board: column j holds the cells
+        // toggled by pressing button j, row i the buttons that toggle cell i
+        let n_cells = width * height;
+        let rows = (0..n_cells)
+            .map(|cell_idx| {
+                let (row, col) = (cell_idx / width, cell_idx % width);
+                (0..n_cells)
+                    .map(|button_idx| {
+                        let (button_row, button_col) = (button_idx / width, button_idx % width);
+                        let toggles = row.abs_diff(button_row) + col.abs_diff(button_col) <= 1;
+                        GFElement::new(toggles as i32, modulus)
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        Matrix::new(rows)
+    }
+
+    fn button_matrix(&self, modulus: i32) -> Matrix {
+        // The toggle matrix without the columns of locked buttons
+        let rows = Self::toggle_matrix(self.width, self.height, modulus)
+            .into_rows()
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .zip(self.locked.iter())
+                    .filter(|(_, locked)| !**locked)
+                    .map(|(element, _)| element)
+                    .collect_vec()
+            })
+            .collect_vec();
+        Matrix::new(rows)
+    }
+
+    fn unlock_solution(&self, solution: Vec<GFElement>) -> Vec<GFElement> {
+        // Spread a solution over the unlocked buttons back onto the whole board,
+        // locked buttons are never pressed
+        let mut presses = solution.into_iter();
+        self.locked
+            .iter()
+            .map(|&locked| match locked {
+                true => GFElement::new(0, self.modulus),
+                false => presses
+                    .next()
+                    .expect("Solution has one entry per unlocked button"),
+            })
+            .collect_vec()
+    }
+
+    pub fn unreachable_targets(width: usize, height: usize) -> Vec<Vec<bool>> {
+        // Return a basis of the orthogonal complement of the column space of the
+        // classic (GF(2)) toggle matrix. A board can be cleared exactly if it is
+        // orthogonal to every basis pattern, so each pattern describes a direction
+        // no sequence of presses can reach.
+        let transposed = Self::toggle_matrix(width, height, 2).transpose();
+        let zeros = Matrix::new(vec![vec![GFElement::new(0, 2)]; width * height]);
+        transposed
+            .concat_horizontal(&zeros)
+            .expect("Zero column has one entry per cell")
+            .null_space()
+            .into_iter()
+            .map(|pattern| pattern.iter().map(|x| x.value != 0).collect_vec())
+            .collect_vec()
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        // Create the augmented matrix. A lit cell needs to advance modulus - 1
+        // states to be turned off, so the target vector is the negated board.
+        // Locked buttons have no column.
+        let target = self
+            .initial
+            .iter()
+            .map(|&lit| vec![GFElement::new(-(lit as i32), self.modulus)])
+            .collect_vec();
+        self.button_matrix(self.modulus)
+            .concat_horizontal(&Matrix::new(target))
+            .expect("Target vector has one entry per cell")
+    }
+
+    pub fn solve(&self) -> Option<Vec<bool>> {
+        // Return the buttons to press to turn all lights off, None if impossible.
+        // A button is either pressed or not, so the puzzle needs modulus 2,
+        // solve_with returns press counts for any modulus. Boards without locked
+        // buttons are solved by chasing the lights, which only eliminates a
+        // width x width system and is much faster than the full matrix solver
+        // (see benches/rref.rs). Boards with locked buttons fall back to the
+        // matrix solver.
+        assert_eq!(
+            self.modulus, 2,
+            "Solving to pressed buttons needs modulus 2"
+        );
+        if !self.locked.iter().any(|&locked| locked) {
+            return self.solve_chase();
+        }
+        self.solve_with(SolveMode::Any)
+            .map(|solution| solution.iter().map(|x| x.value != 0).collect_vec())
+    }
+
+    pub fn solution_coords(&self) -> Option<Vec<(usize, usize)>> {
+        // The (row, col) of every button to press, in reading order. None if the
+        // puzzle cannot be solved.
+        self.solve().map(|presses| {
+            presses
+                .iter()
+                .positions(|&pressed| pressed)
+                .map(|idx| (idx / self.width, idx % self.width))
+                .collect_vec()
+        })
+    }
+
+    pub fn solution_instructions(&self) -> Option<Vec<String>> {
+        // The solution as one sentence per button to press, rows and columns
+        // counted from 0 like in the parse errors
+        self.solution_coords().map(|coords| {
+            coords
+                .iter()
+                .map(|(row, col)| format!("Press button at row {}, column {}", row, col))
+                .collect_vec()
+        })
+    }
+
+    pub fn is_solvable(&self) -> bool {
+        // Whether the board can be cleared, without reading off a solution
+        self.to_matrix().is_consistent()
+    }
+
+    pub fn solve_to(&self, target: &[bool]) -> Option<Vec<bool>> {
+        // Return the buttons to press to turn the board into target, None if
+        // impossible. The presses have to toggle exactly the cells that differ,
+        // so the target vector is the board XOR target.
+        assert_eq!(self.modulus, 2, "Solving to a target needs modulus 2");
+        assert_eq!(
+            target.len(),
+            self.initial.len(),
+            "Target should have one entry per cell"
+        );
+        let rhs = self
+            .initial
+            .iter()
+            .zip(target.iter())
+            .map(|(&lit, &wanted)| vec![GFElement::new((lit ^ wanted) as i32, 2)])
+            .collect_vec();
+        self.button_matrix(2)
+            .concat_horizontal(&Matrix::new(rhs))
+            .expect("Target vector has one entry per cell")
+            .solution()
+            .map(|solution| {
+                self.unlock_solution(solution)
+                    .iter()
+                    .map(|x| x.value != 0)
+                    .collect_vec()
+            })
+    }
+
+    pub fn solve_masked(&self, care: &[bool]) -> Option<Vec<bool>> {
+        // Return the buttons to press to turn off every cell with care[i] set,
+        // the other cells may end up in any state. Their constraint rows are
+        // dropped, so more boards can be solved than with solve. Like solve this
+        // answers with pressed or not, which needs modulus 2.
+        assert_eq!(
+            self.modulus, 2,
+            "Solving to pressed buttons needs modulus 2"
+        );
+        assert_eq!(
+            care.len(),
+            self.initial.len(),
+            "Mask should have one entry per cell"
+        );
+        let rows = self
+            .to_matrix()
+            .into_rows()
+            .into_iter()
+            .zip(care.iter())
+            .filter(|(_, care)| **care)
+            .map(|(row, _)| row)
+            .collect_vec();
+        if rows.is_empty() {
+            return Some(vec![false; self.initial.len()]);
+        }
+        Matrix::new(rows).solution().map(|solution| {
+            self.unlock_solution(solution)
+                .iter()
+                .map(|x| x.value != 0)
+                .collect_vec()
+        })
+    }
+
+    pub fn verify(&self, presses: &[bool], target: &[bool]) -> bool {
+        // Check whether pressing the given buttons (row-major) turns the board
+        // into target
+        let mut board = self.clone();
+        for (idx, _) in presses.iter().enumerate().filter(|(_, pressed)| **pressed) {
+            board.press(idx / self.width, idx % self.width);
+        }
+        board.initial == target
+    }
+
+    pub fn solve_chase(&self) -> Option<Vec<bool>> {
+        // Solve the classic puzzle by chasing the lights: pressing the buttons
+        // below the lit cells of each row clears the board except for the last
+        // row. The first row presses that clear that remainder solve the
+        // chase_lookup_table system, width x width instead of the full board.
+        assert!(
+            !self.locked.iter().any(|&locked| locked),
+            "Chasing the lights does not support locked buttons"
+        );
+        let (width, height) = (self.width, self.height);
+        if width * height == 0 {
+            // Nothing to chase, the empty board is solved without presses
+            return Some(vec![]);
+        }
+        let mut remainder = self.clone();
+        chase(&mut remainder, &mut vec![false; width * height]);
+        let target = remainder.initial[(height - 1) * width..]
+            .iter()
+            .map(|&lit| vec![GFElement::new(lit as i32, 2)])
+            .collect_vec();
+        let first_row = chase_lookup_table(width, height)
+            .concat_horizontal(&Matrix::new(target))
+            .expect("Target vector has one entry per column")
+            .solution_bools()?;
+
+        // Press the first row and chase the lights down the board
+        let mut board = self.clone();
+        let mut presses = vec![false; width * height];
+        for (col, _) in first_row
+            .iter()
+            .enumerate()
+            .filter(|(_, pressed)| **pressed)
+        {
+            board.press(0, col);
+            presses[col] = true;
+        }
+        chase(&mut board, &mut presses);
+        Some(presses)
+    }
+
+    pub fn minimum_presses(&self) -> Option<Vec<GFElement>> {
+        // Return how often to press each button for the fewest presses in total
+        self.solve_with(SolveMode::Minimum)
+    }
+
+    pub fn solve_with(&self, mode: SolveMode) -> Option<Vec<GFElement>> {
+        // Return how often to press each button, trading speed for the number of
+        // presses depending on mode. None if the puzzle cannot be solved.
+        let matrix = self.to_matrix();
+        let solution = match mode {
+            SolveMode::Any => matrix.solution(),
+            SolveMode::Minimum => matrix.minimum_solution(),
+        }?;
+        // Press counts are only meaningful below the press period, pressing a
+        // button modulus times is the same as not pressing it
+        assert!(
+            solution
+                .iter()
+                .all(|x| (0..self.press_period()).contains(&x.value)),
+            "Solution values should be reduced modulo the press period"
+        );
+        Some(self.unlock_solution(solution))
+    }
+
+    pub fn press_period(&self) -> i32 {
+        // Pressing a button this many times returns every light to its state
+        self.modulus
+    }
+
+    pub fn symmetries(&self) -> Vec<Symmetry> {
+        // The rotations and reflections of the board that leave the lit pattern
+        // unchanged, always including the identity
+        Symmetry::ALL
+            .into_iter()
+            .filter(|symmetry| {
+                symmetry
+                    .permutation(self.width, self.height)
+                    .is_some_and(|permutation| {
+                        permutation
+                            .iter()
+                            .enumerate()
+                            .all(|(idx, &image)| self.initial[idx] == self.initial[image])
+                    })
+            })
+            .collect_vec()
+    }
+
+    pub fn overlay(&self, solution: &[GFElement]) -> String {
+        // Draw the board with the buttons to press marked on it, one line per row:
+        // '@' lit and pressed, 'X' lit and not pressed, 'o' off and pressed,
+        // '.' off and not pressed
+        assert_eq!(
+            solution.len(),
+            self.initial.len(),
+            "Solution should have one entry per cell"
+        );
+        if self.width == 0 {
+            // A board without columns has no cells to draw
+            return String::new();
+        }
+        self.initial
+            .iter()
+            .zip(solution.iter())
+            .map(|(&lit, presses)| match (lit, presses.value != 0) {
+                (true, true) => '@',
+                (true, false) => 'X',
+                (false, true) => 'o',
+                (false, false) => '.',
+            })
+            .chunks(self.width)
+            .into_iter()
+            .map(|row| row.collect::<String>())
+            .join("\n")
+    }
+
+    pub fn to_svg(&self, solution: Option<&[bool]>) -> String {
+        // Render the board as a self-contained SVG image: one square per cell,
+        // lit cells filled yellow and off cells dark grey. Buttons to press are
+        // marked with a dot.
+        const CELL_SIZE: usize = 40;
+        if let Some(presses) = solution {
+            assert_eq!(
+                presses.len(),
+                self.initial.len(),
+                "Solution should have one entry per cell"
+            );
+        }
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+            self.width * CELL_SIZE,
+            self.height * CELL_SIZE
+        );
+        for (idx, &lit) in self.initial.iter().enumerate() {
+            let (x, y) = (
+                (idx % self.width) * CELL_SIZE,
+                (idx / self.width) * CELL_SIZE,
+            );
+            let fill = if lit { "#ffd700" } else { "#333333" };
+            svg += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"black\"/>\n",
+                x, y, CELL_SIZE, CELL_SIZE, fill
+            );
+            if solution.is_some_and(|presses| presses[idx]) {
+                svg += &format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"red\"/>\n",
+                    x + CELL_SIZE / 2,
+                    y + CELL_SIZE / 2,
+                    CELL_SIZE / 6
+                );
+            }
+        }
+        svg += "</svg>\n";
+        svg
+    }
+
+    pub fn difficulty(&self) -> Option<u32> {
+        // Heuristic difficulty score, None if the puzzle cannot be solved.
+        // It adds the presses of the minimum solution to the number of free
+        // variables of the system: boards needing more presses are harder, and
+        // every free variable doubles (for GF(2)) the solutions a player has to
+        // choose from, which makes finding the shortest one harder as well.
+        let matrix = self.to_matrix();
+        let min_presses = matrix
+            .minimum_solution()?
+            .iter()
+            .map(|x| x.value as u32)
+            .sum::<u32>();
+        Some(min_presses + matrix.free_columns().len() as u32)
+    }
+
+    pub fn solve_file(path: &Path) -> io::Result<Vec<Option<Vec<bool>>>> {
+        // Read a file of ASCII art boards separated by blank lines and solve each
+        // of them. A board that cannot be parsed yields an InvalidData error
+        // naming the index of the board (counted from 0).
+        let content = fs::read_to_string(path)?;
+        content
+            .lines()
+            .map(|line| line.trim())
+            .chunk_by(|line| line.is_empty())
+            .into_iter()
+            .filter(|(is_blank, _)| !is_blank)
+            .map(|(_, lines)| lines.collect_vec().join("\n"))
+            .collect_vec()
+            .iter()
+            .enumerate()
+            .map(|(board_idx, board)| {
+                board
+                    .parse::<Puzzle>()
+                    .map(|puzzle| puzzle.solve())
+                    .map_err(|err| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Board {}: {}", board_idx, err),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    pub fn solve_batch(
+        width: usize,
+        height: usize,
+        boards: &[Vec<bool>],
+    ) -> Vec<Option<Vec<bool>>> {
+        // Solve many boards of the same size, reducing the toggle matrix only once
+        let solver = Solver::new(width, height);
+        boards.iter().map(|board| solver.solve(board)).collect_vec()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PuzzleRepr {
+    // The serialized form of a puzzle, the board drawn as ASCII art like FromStr
+    // reads it. Missing locked buttons mean none are locked.
+    width: usize,
+    height: usize,
+    board: String,
+    modulus: i32,
+    #[serde(default)]
+    locked: Vec<bool>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Puzzle {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // A board without columns has no cells, its board is the empty string
+        let board = match self.width {
+            0 => String::new(),
+            width => self
+                .initial
+                .chunks(width)
+                .map(|row| {
+                    row.iter()
+                        .map(|&lit| if lit { 'X' } else { '.' })
+                        .collect::<String>()
+                })
+                .join("\n"),
+        };
+        let repr = PuzzleRepr {
+            width: self.width,
+            height: self.height,
+            board,
+            modulus: self.modulus,
+            locked: self.locked.clone(),
+        };
+        serde::Serialize::serialize(&repr, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Puzzle {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Parse the board and check it against the stored dimensions. Parsing
+        // rejects an empty board, so boards without cells are built directly.
+        use serde::de::Error;
+        let repr = <PuzzleRepr as serde::Deserialize>::deserialize(deserializer)?;
+        let puzzle = if repr.width == 0 || repr.height == 0 {
+            if !repr.board.trim().is_empty() {
+                return Err(D::Error::custom(format!(
+                    "Board of {}x{} should be empty",
+                    repr.width, repr.height
+                )));
+            }
+            Puzzle::new(repr.width, repr.height, vec![])
+        } else {
+            repr.board.parse::<Puzzle>().map_err(D::Error::custom)?
+        };
+        let mut puzzle = puzzle.with_modulus(repr.modulus);
+        if (puzzle.width, puzzle.height) != (repr.width, repr.height) {
+            return Err(D::Error::custom(format!(
+                "Board is {}x{}, expected {}x{}",
+                puzzle.width, puzzle.height, repr.width, repr.height
+            )));
+        }
+        if repr.modulus < 2 {
+            return Err(D::Error::custom(format!(
+                "Modulus must be at least 2, got {}",
+                repr.modulus
+            )));
+        }
+        if !repr.locked.is_empty() {
+            if repr.locked.len() != puzzle.initial.len() {
+                return Err(D::Error::custom(format!(
+                    "Board has {} cells, but {} locked entries",
+                    puzzle.initial.len(),
+                    repr.locked.len()
+                )));
+            }
+            puzzle.with_locked_buttons(&repr.locked);
+        }
+        Ok(puzzle)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SolveMode {
+    // Any valid solution, read off the reduced matrix with all free variables
+    // set to 0
+    Any,
+    // The solution with the fewest presses, found by enumerating the null space
+    Minimum,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BoardAnalysis {
+    // Facts about the classic (GF(2)) puzzle of a board size. The counts are
+    // None if they do not fit into a u128.
+    pub rank: usize,
+    // Dimension of the null space of the toggle matrix
+    pub nullity: usize,
+    // Number of press patterns leaving every board unchanged, 2^nullity
+    pub quiet_patterns: Option<u128>,
+    // Number of solvable starting boards, 2^rank
+    pub solvable_configurations: Option<u128>,
+}
+
+pub fn board_analysis(width: usize, height: usize) -> BoardAnalysis {
+    // Analyze the toggle matrix of a width x height board
+    let rank = Puzzle::toggle_matrix(width, height, 2).rank();
+    let nullity = width * height - rank;
+    BoardAnalysis {
+        rank,
+        nullity,
+        quiet_patterns: 2u128.checked_pow(nullity as u32),
+        solvable_configurations: 2u128.checked_pow(rank as u32),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Symmetry {
+    // The 8 elements of the dihedral group D4 acting on the board. Rotations are
+    // clockwise, FlipHorizontal mirrors left and right, FlipDiagonal transposes
+    // along the main diagonal.
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    FlipDiagonal,
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    fn permutation(self, width: usize, height: usize) -> Option<Vec<usize>> {
+        // The symmetry as a cell permutation, entry i holding the cell that cell
+        // i is mapped to. None if it does not keep the shape of the board, which
+        // only the rotations by 180 degrees and the flips along an axis do for
+        // non-square boards.
+        let keeps_shape = matches!(
+            self,
+            Symmetry::Identity
+                | Symmetry::Rotate180
+                | Symmetry::FlipHorizontal
+                | Symmetry::FlipVertical
+        );
+        if !keeps_shape && width != height {
+            return None;
+        }
+        if width == 0 || height == 0 {
+            // An empty board has no cells to move
+            return Some(vec![]);
+        }
+        let (last_row, last_col) = (height - 1, width - 1);
+        let map = |row: usize, col: usize| match self {
+            Symmetry::Identity => (row, col),
+            Symmetry::Rotate90 => (col, last_row - row),
+            Symmetry::Rotate180 => (last_row - row, last_col - col),
+            Symmetry::Rotate270 => (last_col - col, row),
+            Symmetry::FlipHorizontal => (row, last_col - col),
+            Symmetry::FlipVertical => (last_row - row, col),
+            Symmetry::FlipDiagonal => (col, row),
+            Symmetry::FlipAntiDiagonal => (last_col - col, last_row - row),
+        };
+        let permutation = (0..width * height)
+            .map(|idx| {
+                let (row, col) = map(idx / width, idx % width);
+                row * width + col
+            })
+            .collect_vec();
+        Some(permutation)
+    }
+}
+
+fn dihedral_permutations(width: usize, height: usize) -> Vec<Vec<usize>> {
+    // The symmetries of the board as cell permutations. A square board has the 8
+    // rotations and reflections of the dihedral group, any other board only the
+    // 4 that keep its shape.
+    Symmetry::ALL
+        .iter()
+        .filter_map(|symmetry| symmetry.permutation(width, height))
+        .collect_vec()
+}
+
+pub fn distinct_solvable_boards(width: usize, height: usize) -> u64 {
+    // Count the solvable starting boards of the classic (GF(2)) puzzle, boards
+    // that are rotations or reflections of each other counted once.
+    // By Burnside's lemma this is the average number of solvable boards fixed by
+    // a symmetry. The solvable boards are the column space of the toggle matrix
+    // A. A symmetry P commutes with A, so the boards it fixes are A x with
+    // (P - I) A x = 0, a subspace of dimension rank(A) - rank((P - I) A).
+    let toggle = Puzzle::toggle_matrix(width, height, 2);
+    let rank = toggle.rank();
+    let permutations = dihedral_permutations(width, height);
+
+    let fixed_boards = permutations
+        .iter()
+        .map(|permutation| {
+            let rows = permutation
+                .iter()
+                .enumerate()
+                .map(|(idx, &image)| {
+                    toggle.rows[image]
+                        .iter()
+                        .zip(toggle.rows[idx].iter())
+                        .map(|(a, b)| *a - *b)
+                        .collect_vec()
+                })
+                .collect_vec();
+            let fixed_dim = rank - Matrix::new(rows).rank();
+            1u128
+                .checked_shl(fixed_dim as u32)
+                .expect("Board count should fit into a u128")
+        })
+        .sum::<u128>();
+    (fixed_boards / permutations.len() as u128)
+        .try_into()
+        .expect("Board count should fit into a u64")
+}
+
+fn chase(board: &mut Puzzle, presses: &mut [bool]) {
+    // Chase the lights down the board: press the button below every lit cell,
+    // row by row, which leaves only the last row lit. presses records the
+    // buttons pressed.
+    let width = board.width;
+    for row in 1..board.height {
+        for col in 0..width {
+            if board.initial[(row - 1) * width + col] {
+                board.press(row, col);
+                presses[row * width + col] ^= true;
+            }
+        }
+    }
+}
+
+pub fn chase_lookup_table(width: usize, height: usize) -> Matrix {
+    // The GF(2) matrix mapping first row presses to the last row that is left
+    // lit after chasing the lights on an empty width x height board: column j
+    // holds the remainder of pressing first row button j. The remainder does
+    // not only depend on the width, the chase runs through all rows.
+    let n_cells = width * height;
+    if n_cells == 0 {
+        // An empty board has no last row to map onto
+        return Matrix::new(vec![]);
+    }
+    let remainders = (0..width)
+        .map(|col| {
+            let mut board = Puzzle::new(width, height, vec![false; n_cells]);
+            board.press(0, col);
+            chase(&mut board, &mut vec![false; n_cells]);
+            board.initial[(height - 1) * width..].to_vec()
+        })
+        .collect_vec();
+    let rows = (0..width)
+        .map(|cell| {
+            remainders
+                .iter()
+                .map(|remainder| GFElement::new(remainder[cell] as i32, 2))
+                .collect_vec()
+        })
+        .collect_vec();
+    Matrix::new(rows)
+}
+
+pub fn min_press_histogram(width: usize, height: usize) -> Vec<u64> {
+    // Count the solvable boards of the classic (GF(2)) puzzle by the size of
+    // their minimum solution, index k holding the boards that need k presses.
+    // Every press combination is tried and the fewest presses seen for each
+    // board kept, so this is only feasible for small boards.
+    let n_cells = width * height;
+    assert!(
+        n_cells <= 24,
+        "Histogram is only feasible for boards of up to 24 cells"
+    );
+    let toggle = Puzzle::toggle_matrix(width, height, 2);
+    let effects = (0..n_cells)
+        .map(|button| {
+            (0..n_cells)
+                .filter(|&cell| toggle.rows[cell][button].value != 0)
+                .fold(0usize, |mask, cell| mask | 1 << cell)
+        })
+        .collect_vec();
+
+    let mut min_presses = vec![u8::MAX; 1 << n_cells];
+    for presses in 0..1usize << n_cells {
+        let board = (0..n_cells)
+            .filter(|button| presses >> button & 1 == 1)
+            .fold(0, |board, button| board ^ effects[button]);
+        let count = presses.count_ones() as u8;
+        min_presses[board] = min_presses[board].min(count);
+    }
+
+    let mut histogram = vec![];
+    for &count in min_presses.iter().filter(|&&count| count != u8::MAX) {
+        if histogram.len() <= count as usize {
+            histogram.resize(count as usize + 1, 0);
+        }
+        histogram[count as usize] += 1;
+    }
+    histogram
+}
+
+pub fn boards_with_min_presses(width: usize, height: usize, k: usize) -> Vec<Vec<bool>> {
+    // All boards of the classic (GF(2)) puzzle whose minimum solution presses
+    // exactly k buttons. Every set of at most k buttons is pressed on an empty
+    // board, boards already reached with fewer presses are left out.
+    let n_cells = width * height;
+    let mut reached = HashSet::new();
+    let mut boards = vec![];
+    for weight in 0..=k.min(n_cells) {
+        for buttons in (0..n_cells).combinations(weight) {
+            let mut board = Puzzle::new(width, height, vec![false; n_cells]);
+            for button in buttons {
+                board.press(button / width, button % width);
+            }
+            if reached.insert(board.initial.clone()) && weight == k {
+                boards.push(board.initial);
+            }
+        }
+    }
+    boards
+}
+
+pub struct Solver {
+    // Solves boards of a fixed size over GF(2), the toggle matrix is reduced once
+    // on construction.
+    // The toggle matrix A is augmented with the identity and reduced to [R | E],
+    // E recording the row operations so that E * A = R. For every board b the
+    // reduced target is E * b, which is consistent if it vanishes on the zero
+    // rows of R. The pivot variables then take the values of E * b, the free
+    // variables are left at 0.
+    width: usize,
+    height: usize,
+    reduced: Matrix,
+    pivots: Vec<Option<usize>>,
+}
+
+impl Solver {
+    pub fn new(width: usize, height: usize) -> Self {
+        let n_cells = width * height;
+        let mut reduced = Puzzle::toggle_matrix(width, height, 2)
+            .concat_horizontal(&Matrix::identity(n_cells, 2))
+            .expect("Identity has one row per cell");
+        reduced.to_rref_mut();
+
+        let pivots = reduced
+            .rows
+            .iter()
+            .map(|row| row.iter().take(n_cells).position(|x| x.value != 0))
+            .collect_vec();
+
+        Solver {
+            width,
+            height,
+            reduced,
+            pivots,
+        }
+    }
+
+    pub fn solve(&self, initial: &[bool]) -> Option<Vec<bool>> {
+        // Return the buttons to press to turn all lights off, None if impossible
+        let n_cells = self.width * self.height;
+        assert_eq!(
+            initial.len(),
+            n_cells,
+            "Board has the wrong number of cells"
+        );
+
+        let mut solution = vec![false; n_cells];
+        for (row, pivot) in self.reduced.rows.iter().zip(self.pivots.iter()) {
+            // Parity of the row of E applied to the board
+            let value = row
+                .iter()
+                .skip(n_cells)
+                .zip(initial.iter())
+                .filter(|(x, lit)| x.value != 0 && **lit)
+                .count()
+                % 2
+                == 1;
+            match pivot {
+                Some(pivot_idx) => solution[*pivot_idx] = value,
+                // A zero row of R with a non-zero target is a contradiction
+                None if value => return None,
+                None => {}
+            }
+        }
+        Some(solution)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GraphPuzzle {
+    // Lights Out on an arbitrary graph: pressing a vertex toggles the light of
+    // the vertex and of its neighbours adjacency[vertex]. Lights only have the
+    // states on and off, so the puzzle is always solved over GF(2).
+    pub adjacency: Vec<Vec<usize>>,
+    pub lit: Vec<bool>,
+}
+
+impl GraphPuzzle {
+    pub fn new(adjacency: Vec<Vec<usize>>, lit: Vec<bool>) -> Self {
+        assert_eq!(
+            adjacency.len(),
+            lit.len(),
+            "Graph should have one light per vertex"
+        );
+        GraphPuzzle { adjacency, lit }
+    }
+
+    pub fn press(&mut self, vertex: usize) {
+        // Press the button of vertex, toggling it and its neighbours
+        self.lit[vertex] = !self.lit[vertex];
+        for &neighbour in self.adjacency[vertex].iter() {
+            self.lit[neighbour] = !self.lit[neighbour];
+        }
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        // Create the toggle matrix augmented with the target, negated lights
+        let target = self
+            .lit
+            .iter()
+            .map(|&lit| vec![GFElement::new(-(lit as i32), 2)])
+            .collect_vec();
+        Matrix::from_adjacency(&self.adjacency, 2)
+            .concat_horizontal(&Matrix::new(target))
+            .expect("Target vector has one entry per vertex")
+    }
+
+    pub fn solve(&self) -> Option<Vec<bool>> {
+        // Return the vertices to press to turn all lights off, None if impossible
+        self.to_matrix().solution_bools()
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Parse a board drawn as ASCII art, 'X' for a lit cell and '.' for an off
+        // cell. Blank lines and surrounding whitespace are ignored.
+        let lines = s
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect_vec();
+        let grid = lines
+            .iter()
+            .enumerate()
+            .map(|(row_idx, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(|(col_idx, cell)| match cell {
+                        'X' => Ok(true),
+                        '.' => Ok(false),
+                        _ => Err(ParseError::InvalidCell {
+                            row: row_idx,
+                            col: col_idx,
+                            found: cell,
+                        }),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Puzzle::from_grid(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BoardAnalysis, GraphPuzzle, Puzzle, SolveMode, Solver, Symmetry, board_analysis,
+        boards_with_min_presses, chase_lookup_table, dihedral_permutations,
+        distinct_solvable_boards, min_press_histogram,
+    };
+    use crate::error::ParseError;
+    use crate::finite_field::GFElement;
+    use crate::linalg::{Matrix, dot};
+    use itertools::Itertools;
+    use proptest::prelude::*;
+    use rstest::rstest;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        // Write content to a file in the temporary directory
+        let path = std::env::temp_dir().join(format!(
+            "lights_out_solver_{}_{}.txt",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, content).expect("Temporary file should be writable");
+        path
+    }
+
+    fn apply_presses(puzzle: &Puzzle, presses: &[bool]) -> Vec<bool> {
+        // Press the given buttons and return the resulting board
+        let matrix = Puzzle::toggle_matrix(puzzle.width, puzzle.height, 2);
+        puzzle
+            .initial
+            .iter()
+            .zip(matrix.rows.iter())
+            .map(|(&lit, row)| {
+                let toggles = row
+                    .iter()
+                    .zip(presses.iter())
+                    .filter(|(x, pressed)| x.value != 0 && **pressed)
+                    .count();
+                lit ^ (toggles % 2 == 1)
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_toggle_matrix() {
+        assert_eq!(
+            format!("{}", Puzzle::toggle_matrix(2, 2, 2)),
+            "1 1 1 0\n1 1 0 1\n1 0 1 1\n0 1 1 1"
+        );
+    }
+
+    #[test]
+    fn test_strip_toggle_matrix() {
+        let puzzle = Puzzle::strip(4, &[false; 4]);
+        assert_eq!((puzzle.width, puzzle.height), (4, 1));
+        assert_eq!(
+            format!("{}", Puzzle::toggle_matrix(4, 1, 2)),
+            "1 1 0 0\n1 1 1 0\n0 1 1 1\n0 0 1 1"
+        );
+    }
+
+    #[rstest]
+    #[case::middle(&[false, true, true, true, false], &[false, false, true, false, false])]
+    #[case::left(&[true, true, true, false, false], &[false, true, false, false, false])]
+    #[case::ends(&[true, true, false, true, true], &[false, true, false, true, false])]
+    fn test_solve_strip(#[case] initial: &[bool], #[case] expected: &[bool]) {
+        let puzzle = Puzzle::strip(5, initial);
+        assert_eq!(puzzle.solve(), Some(expected.to_vec()));
+        assert!(puzzle.verify(expected, &[false; 5]));
+    }
+
+    #[test]
+    fn test_toggle_matrix_nonzero_count() {
+        // Every cell toggles itself, and each of the 40 adjacent pairs of cells
+        // adds two entries
+        let matrix = Puzzle::toggle_matrix(5, 5, 2);
+        assert_eq!(matrix.nonzero_count(), 25 + 2 * 40);
+        assert_eq!(matrix.density(), 105.0 / 625.0);
+        assert_eq!(Matrix::new(vec![]).density(), 0.0);
+    }
+
+    #[test]
+    fn test_toggle_matrix_symmetric() {
+        // Every button toggles itself, so the trace counts the 9 cells
+        let matrix = Puzzle::toggle_matrix(3, 3, 2);
+        assert!(matrix.is_symmetric());
+        assert_eq!(matrix.trace(), Ok(GFElement::new(1, 2)));
+    }
+
+    #[rstest]
+    #[case::center_press(3, 3, vec![4])]
+    #[case::corner_press(3, 3, vec![0])]
+    #[case::two_presses(4, 2, vec![1, 6])]
+    fn test_solve(#[case] width: usize, #[case] height: usize, #[case] pressed: Vec<usize>) {
+        // Construct the board by pressing buttons on an all-off board, the solver
+        // should find presses that turn it off again
+        let presses = (0..width * height)
+            .map(|idx| pressed.contains(&idx))
+            .collect_vec();
+        let off = Puzzle::new(width, height, vec![false; width * height]);
+        let puzzle = Puzzle::new(width, height, apply_presses(&off, &presses));
+
+        let solution = puzzle.solve().expect("Board should be solvable");
+        assert!(apply_presses(&puzzle, &solution).iter().all(|&lit| !lit));
+    }
+
+    #[test]
+    fn test_solve_unsolvable() {
+        // On a 5x5 board a single lit corner cannot be turned off
+        let mut initial = vec![false; 25];
+        initial[0] = true;
+        let puzzle = Puzzle::new(5, 5, initial);
+        assert_eq!(puzzle.solve(), None);
+        assert!(!puzzle.is_solvable());
+    }
+
+    #[test]
+    fn test_solve_batch() {
+        let boards = (0..16)
+            .map(|seed| {
+                (0..25)
+                    .map(|idx| (idx * 7 + seed * 3) % 5 < 2)
+                    .collect_vec()
+            })
+            .collect_vec();
+        let expected = boards
+            .iter()
+            .map(|board| matrix_solve(&Puzzle::new(5, 5, board.clone())))
+            .collect_vec();
+
+        assert_eq!(Puzzle::solve_batch(5, 5, &boards), expected);
+        // Make sure both solvable and unsolvable boards are covered
+        assert!(expected.iter().any(|solution| solution.is_some()));
+        assert!(expected.iter().any(|solution| solution.is_none()));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let puzzle: Puzzle = ".X.\nXX.\n".parse().unwrap();
+        assert_eq!((puzzle.width, puzzle.height), (3, 2));
+        assert_eq!(puzzle.initial, vec![false, true, false, true, true, false]);
+    }
+
+    #[test]
+    fn test_eq_and_clone() {
+        let board = ".X.\nXX.\n";
+        let puzzle: Puzzle = board.parse().unwrap();
+        assert_eq!(puzzle, board.parse::<Puzzle>().unwrap());
+        assert_eq!(puzzle.clone(), puzzle);
+        assert_ne!(puzzle, "XX.\nXX.\n".parse::<Puzzle>().unwrap());
+        assert_ne!(puzzle.clone().with_modulus(3), puzzle);
+    }
+
+    #[rstest]
+    #[case::empty("", ParseError::Empty)]
+    #[case::ragged("X.\nX", ParseError::RaggedRow { row: 1, expected: 2, found: 1 })]
+    #[case::invalid_cell("X.\nXo", ParseError::InvalidCell { row: 1, col: 1, found: 'o' })]
+    fn test_from_str_invalid(#[case] board: &str, #[case] expected: ParseError) {
+        assert_eq!(board.parse::<Puzzle>().err(), Some(expected));
+    }
+
+    #[test]
+    fn test_minimum_presses() {
+        // Pressing the center of a 3x3 board over GF(3) lights the cross once,
+        // pressing the center twice more turns it off again
+        let puzzle = ".X.\nXXX\n.X.".parse::<Puzzle>().unwrap().with_modulus(3);
+        let mut expected = vec![GFElement::new(0, 3); 9];
+        expected[4] = GFElement::new(2, 3);
+        assert_eq!(puzzle.minimum_presses(), Some(expected));
+    }
+
+    #[test]
+    #[should_panic(expected = "Solving to pressed buttons needs modulus 2")]
+    fn test_solve_needs_modulus_two() {
+        // Over GF(3) the cross needs two presses of the center, which a
+        // pressed/not pressed answer cannot express
+        let puzzle = ".X.\nXXX\n.X.".parse::<Puzzle>().unwrap().with_modulus(3);
+        puzzle.solve();
+    }
+
+    #[test]
+    fn test_solve_with_modulus_three_clears_board() {
+        // Applying the press counts over GF(3) turns every light off: each cell
+        // ends up at its initial state plus the presses of the buttons toggling it
+        let puzzle = "X.X\n.X.\nXX.".parse::<Puzzle>().unwrap().with_modulus(3);
+        let presses = puzzle.solve_with(SolveMode::Any).unwrap();
+        let toggle = Puzzle::toggle_matrix(3, 3, 3);
+        for (row, &lit) in toggle.rows.iter().zip(puzzle.initial.iter()) {
+            assert_eq!(dot(row, &presses) + lit as i32, GFElement::new(0, 3));
+        }
+    }
+
+    #[rstest]
+    #[case(3, 3)]
+    #[case(4, 4)]
+    #[case(5, 5)]
+    fn test_is_solvable(#[case] width: usize, #[case] height: usize) {
+        for seed in 0..8 {
+            let initial = (0..width * height)
+                .map(|idx| (idx * 7 + seed) % 5 < 2)
+                .collect_vec();
+            let puzzle = Puzzle::new(width, height, initial);
+            assert_eq!(puzzle.is_solvable(), puzzle.solve().is_some());
+        }
+    }
+
+    fn matrix_solve(puzzle: &Puzzle) -> Option<Vec<bool>> {
+        // Solve with the matrix solver, bypassing the light chasing dispatch
+        puzzle
+            .solve_with(SolveMode::Any)
+            .map(|solution| solution.iter().map(|x| x.value != 0).collect_vec())
+    }
+
+    #[rstest]
+    #[case(5, 5)]
+    #[case(4, 4)]
+    #[case(3, 6)]
+    #[case(1, 4)]
+    fn test_solve_chase(#[case] width: usize, #[case] height: usize) {
+        for seed in 0..10 {
+            let initial = (0..width * height)
+                .map(|idx| (idx * 13 + seed * 7) % 5 < 2)
+                .collect_vec();
+            let puzzle = Puzzle::new(width, height, initial);
+            let chased = puzzle.solve_chase();
+            assert_eq!(
+                chased.is_some(),
+                puzzle.solve_with(SolveMode::Any).is_some()
+            );
+            if let Some(presses) = chased {
+                assert!(puzzle.verify(&presses, &vec![false; width * height]));
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(3, 0)]
+    #[case(0, 3)]
+    #[case(0, 0)]
+    fn test_solve_chase_empty(#[case] width: usize, #[case] height: usize) {
+        let puzzle = Puzzle::new(width, height, vec![]);
+        assert_eq!(puzzle.solve_chase(), Some(vec![]));
+        assert_eq!(puzzle.solve(), Some(vec![]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut puzzle: Puzzle = "X.X\n.XX".parse().unwrap();
+        puzzle.with_locked_buttons(&[false, true, false, false, false, false]);
+        let puzzle = puzzle.with_modulus(3);
+        let json = serde_json::to_string(&puzzle).unwrap();
+        assert!(json.contains(r#""board":"X.X\n.XX""#));
+        assert_eq!(serde_json::from_str::<Puzzle>(&json).unwrap(), puzzle);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_invalid_dimensions() {
+        let json = r#"{"width":3,"height":3,"board":"X.X\n.XX","modulus":2}"#;
+        let err = serde_json::from_str::<Puzzle>(json).unwrap_err();
+        assert!(err.to_string().contains("Board is 3x2, expected 3x3"));
+
+        let json = r#"{"width":2,"height":1,"board":"Xo","modulus":2}"#;
+        assert!(serde_json::from_str::<Puzzle>(json).is_err());
+
+        let json = r#"{"width":0,"height":3,"board":"X","modulus":2}"#;
+        let err = serde_json::from_str::<Puzzle>(json).unwrap_err();
+        assert!(err.to_string().contains("Board of 0x3 should be empty"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    #[case(0, 3)]
+    #[case(3, 0)]
+    #[case(0, 0)]
+    fn test_serde_empty_board(#[case] width: usize, #[case] height: usize) {
+        let puzzle = Puzzle::new(width, height, vec![]);
+        let json = serde_json::to_string(&puzzle).unwrap();
+        assert!(json.contains(r#""board":"""#));
+        assert_eq!(serde_json::from_str::<Puzzle>(&json).unwrap(), puzzle);
+    }
+
+    #[test]
+    fn test_chase_lookup_table() {
+        let table = chase_lookup_table(5, 5);
+        assert_eq!((table.n_rows(), table.n_cols()), (5, 5));
+        // Pressing the top left button leaves cells 1, 2 and 4 of the last row lit
+        assert_eq!(
+            format!("{}", table),
+            "0 1 1 0 1\n1 1 1 0 0\n1 1 0 1 1\n0 0 1 1 1\n1 0 1 1 0"
+        );
+        // The table is singular exactly where the board has quiet patterns
+        assert_eq!(table.rank(), 3);
+        assert_eq!(chase_lookup_table(5, 4).rank(), 5);
+        assert_eq!(chase_lookup_table(5, 0), Matrix::default());
+        assert_eq!(chase_lookup_table(0, 5), Matrix::default());
+    }
+
+    #[rstest]
+    #[case(5, 5)]
+    #[case(7, 4)]
+    fn test_solve_dispatch_agrees(#[case] width: usize, #[case] height: usize) {
+        // Random solvable boards: press random buttons on an empty board
+        for seed in 0..10 {
+            let mut puzzle = Puzzle::new(width, height, vec![false; width * height]);
+            for idx in (0..width * height).filter(|idx| (idx * 17 + seed * 5) % 7 < 3) {
+                puzzle.press(idx / width, idx % width);
+            }
+            let target = vec![false; width * height];
+            let chased = puzzle.solve().expect("Board is solvable");
+            assert!(puzzle.verify(&chased, &target));
+
+            let matrix = matrix_solve(&puzzle).expect("Board is solvable");
+            assert!(puzzle.verify(&matrix, &target));
+        }
+    }
+
+    #[test]
+    fn test_solution_instructions() {
+        // Pressing the lit corner and its two neighbours toggles the corner three
+        // times and every other cell twice
+        let puzzle: Puzzle = "X.\n..".parse().unwrap();
+        assert_eq!(puzzle.solution_coords(), Some(vec![(0, 0), (0, 1), (1, 0)]));
+        assert_eq!(
+            puzzle.solution_instructions().unwrap(),
+            vec![
+                "Press button at row 0, column 0",
+                "Press button at row 0, column 1",
+                "Press button at row 1, column 0",
+            ]
+        );
+
+        let unsolvable: Puzzle = "X....\n.....\n.....\n.....\n.....".parse().unwrap();
+        assert_eq!(unsolvable.solution_instructions(), None);
+    }
+
+    #[test]
+    fn test_solve_values_in_range() {
+        let puzzle: Puzzle = "X.X\n.X.\nXX.".parse().unwrap();
+        let puzzle = puzzle.with_modulus(3);
+        assert_eq!(puzzle.press_period(), 3);
+        for mode in [SolveMode::Any, SolveMode::Minimum] {
+            let solution = puzzle.solve_with(mode).unwrap();
+            assert!(solution.iter().all(|x| (0..3).contains(&x.value)));
+            assert!(solution.iter().any(|x| x.value == 2));
+        }
+    }
+
+    #[test]
+    fn test_solve_masked() {
+        // A single corner light cannot be cleared on the 5x5 board, but it can
+        // once the opposite corner may stay lit
+        let mut initial = vec![false; 25];
+        initial[0] = true;
+        let puzzle = Puzzle::new(5, 5, initial);
+        assert_eq!(puzzle.solve(), None);
+        assert_eq!(puzzle.solve_masked(&[true; 25]), None);
+
+        let mut care = vec![true; 25];
+        care[24] = false;
+        let presses = puzzle.solve_masked(&care).unwrap();
+        let mut board = puzzle.clone();
+        for idx in (0..25).filter(|&idx| presses[idx]) {
+            board.press(idx / 5, idx % 5);
+        }
+        assert!(board.initial.iter().take(24).all(|&lit| !lit));
+        assert!(board.initial[24]);
+
+        assert_eq!(puzzle.solve_masked(&[false; 25]), Some(vec![false; 25]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Solving to pressed buttons needs modulus 2")]
+    fn test_solve_masked_needs_modulus_two() {
+        let puzzle = ".X.\nXXX\n.X.".parse::<Puzzle>().unwrap().with_modulus(3);
+        puzzle.solve_masked(&[true; 9]);
+    }
+
+    #[rstest]
+    #[case(3, 3)]
+    #[case(4, 5)]
+    fn test_solve_to_all_on(#[case] width: usize, #[case] height: usize) {
+        let initial = (0..width * height).map(|idx| idx % 3 == 0).collect_vec();
+        let puzzle = Puzzle::new(width, height, initial);
+        let all_on = vec![true; width * height];
+        let presses = puzzle.solve_to(&all_on).unwrap();
+        assert!(puzzle.verify(&presses, &all_on));
+        assert!(!puzzle.verify(&presses, &vec![false; width * height]));
+    }
+
+    #[test]
+    fn test_solve_to_all_off() {
+        let puzzle = ".X.\nXXX\n.X.".parse::<Puzzle>().unwrap();
+        let all_off = vec![false; 9];
+        assert_eq!(puzzle.solve_to(&all_off), puzzle.solve());
+        assert!(puzzle.verify(&puzzle.solve().unwrap(), &all_off));
+    }
+
+    #[test]
+    fn test_minimum_presses_tie_break() {
+        // Pressing either button of a 1x2 board toggles both lights. Of the two
+        // single press solutions the lexicographically smaller one is returned.
+        let puzzle = "XX".parse::<Puzzle>().unwrap();
+        assert_eq!(
+            puzzle.minimum_presses(),
+            Some(vec![GFElement::new(0, 2), GFElement::new(1, 2)])
+        );
+    }
+
+    #[rstest]
+    #[case(3, 3, 0)]
+    #[case(4, 4, 4)]
+    #[case(5, 5, 2)]
+    fn test_unreachable_targets(#[case] width: usize, #[case] height: usize, #[case] dim: usize) {
+        let patterns = Puzzle::unreachable_targets(width, height);
+        assert_eq!(patterns.len(), dim);
+        assert_eq!(dim, board_analysis(width, height).nullity);
+        let n_cells = width * height;
+        for pattern in patterns {
+            // Every board overlapping the pattern in an odd number of cells is
+            // unsolvable, e.g. a single light in the pattern. Over GF(2) the
+            // pattern itself can be orthogonal to the whole basis, so it is not
+            // necessarily unsolvable.
+            let cell_idx = pattern.iter().position(|&lit| lit).unwrap();
+            let mut initial = vec![false; n_cells];
+            initial[cell_idx] = true;
+            assert!(!Puzzle::new(width, height, initial).is_solvable());
+
+            // As the toggle matrix is symmetric the pattern is also a quiet pattern
+            let board = Puzzle::new(width, height, vec![false; n_cells]);
+            assert!(board.verify(&pattern, &vec![false; n_cells]));
+        }
+    }
+
+    #[test]
+    fn test_with_locked_buttons() {
+        // The cross is only cleared by pressing the center of a 3x3 board
+        let mut puzzle = ".X.\nXXX\n.X.".parse::<Puzzle>().unwrap();
+        let mut locked = vec![false; 9];
+        locked[4] = true;
+        puzzle.with_locked_buttons(&locked);
+        assert_eq!(puzzle.to_matrix().n_cols(), 9);
+        assert!(!puzzle.is_solvable());
+        assert_eq!(puzzle.solve(), None);
+    }
+
+    #[test]
+    fn test_with_locked_buttons_solution() {
+        // On a 1x2 board either button clears both lights, with the first one
+        // locked the second one has to be pressed
+        let mut puzzle = "XX".parse::<Puzzle>().unwrap();
+        assert_eq!(puzzle.solve(), Some(vec![true, false]));
+        puzzle.with_locked_buttons(&[true, false]);
+        assert_eq!(puzzle.solve(), Some(vec![false, true]));
+        assert_eq!(
+            puzzle.minimum_presses(),
+            Some(vec![GFElement::new(0, 2), GFElement::new(1, 2)])
+        );
+        assert_eq!(puzzle.solve_to(&[false, false]), Some(vec![false, true]));
+    }
+
+    #[rstest]
+    #[case(3, 3)]
+    #[case(4, 2)]
+    #[case(1, 5)]
+    fn test_to_svg(#[case] width: usize, #[case] height: usize) {
+        let initial = (0..width * height).map(|idx| idx % 2 == 0).collect_vec();
+        let puzzle = Puzzle::new(width, height, initial);
+        let svg = puzzle.to_svg(None);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect").count(), width * height);
+        assert_eq!(svg.matches("<circle").count(), 0);
+    }
+
+    #[test]
+    fn test_to_svg_solution() {
+        let puzzle = ".X.\nXXX\n.X.".parse::<Puzzle>().unwrap();
+        let solution = puzzle.solve().unwrap();
+        let svg = puzzle.to_svg(Some(&solution));
+        assert_eq!(svg.matches("<rect").count(), 9);
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert_eq!(svg.matches("#ffd700").count(), 5);
+    }
+
+    #[test]
+    fn test_overlay() {
+        let puzzle = ".X.\nXXX\n.X.".parse::<Puzzle>().unwrap();
+        let solution = puzzle.solve_with(SolveMode::Any).unwrap();
+        assert_eq!(puzzle.overlay(&solution), ".X.\nX@X\n.X.");
+
+        let puzzle = "X.\n..".parse::<Puzzle>().unwrap();
+        let solution = [0, 1, 1, 0].map(|value| GFElement::new(value, 2));
+        assert_eq!(puzzle.overlay(&solution), "Xo\no.");
+
+        assert_eq!(Puzzle::new(0, 3, vec![]).overlay(&[]), "");
+        assert_eq!(Puzzle::new(3, 0, vec![]).overlay(&[]), "");
+    }
+
+    #[test]
+    fn test_solve_with() {
+        // The 4x4 toggle matrix has a 4-dimensional null space. Every board lit by
+        // a single press needs exactly one press, which the particular solution
+        // does not always find.
+        let presses = |solution: Vec<GFElement>| solution.iter().filter(|x| x.value != 0).count();
+        let mut any_presses = vec![];
+        for idx in 0..16 {
+            let mut puzzle = Puzzle::new(4, 4, vec![false; 16]);
+            puzzle.press(idx / 4, idx % 4);
+            let any = puzzle.solve_with(SolveMode::Any).map(presses).unwrap();
+            let minimum = puzzle.solve_with(SolveMode::Minimum).map(presses).unwrap();
+            assert_eq!(minimum, 1);
+            assert!(minimum <= any);
+            any_presses.push(any);
+        }
+        assert!(any_presses.iter().any(|&count| count > 1));
+    }
+
+    #[rstest]
+    #[case(3, 3)]
+    #[case(4, 4)]
+    #[case(5, 5)]
+    #[case(2, 3)]
+    fn test_solver(#[case] width: usize, #[case] height: usize) {
+        let solver = Solver::new(width, height);
+        for seed in 0..8 {
+            let initial = (0..width * height)
+                .map(|idx| (idx * 5 + seed * 3) % 4 == 0)
+                .collect_vec();
+            let expected = matrix_solve(&Puzzle::new(width, height, initial.clone()));
+            assert_eq!(solver.solve(&initial), expected);
+        }
+    }
+
+    #[test]
+    fn test_from_grid() {
+        let puzzle =
+            Puzzle::from_grid(vec![vec![true, false, true], vec![false, true, true]]).unwrap();
+        assert_eq!((puzzle.width, puzzle.height), (3, 2));
+        assert_eq!(puzzle.initial, vec![true, false, true, false, true, true]);
+    }
+
+    #[rstest]
+    #[case::empty(vec![], ParseError::Empty)]
+    #[case::empty_row(vec![vec![]], ParseError::Empty)]
+    #[case::empty_rows(vec![vec![], vec![]], ParseError::Empty)]
+    #[case::ragged(
+        vec![vec![true, false], vec![true, false], vec![true]],
+        ParseError::RaggedRow { row: 2, expected: 2, found: 1 }
+    )]
+    fn test_from_grid_invalid(#[case] grid: Vec<Vec<bool>>, #[case] expected: ParseError) {
+        assert_eq!(Puzzle::from_grid(grid).err(), Some(expected));
+    }
+
+    #[test]
+    fn test_difficulty() {
+        let off = Puzzle::new(4, 4, vec![false; 16]);
+        let dense = Puzzle::new(4, 4, (0..16).map(|idx| idx % 5 != 0).collect_vec());
+        let off_score = off.difficulty().unwrap();
+        let dense_score = dense.difficulty().unwrap();
+        // The 4x4 board has 4 free variables, the off board needs no presses
+        assert_eq!(off_score, 4);
+        assert!(off_score < dense_score);
+    }
+
+    #[test]
+    fn test_difficulty_unsolvable() {
+        let mut initial = vec![false; 25];
+        initial[0] = true;
+        assert_eq!(Puzzle::new(5, 5, initial).difficulty(), None);
+    }
+
+    #[test]
+    fn test_solve_file() {
+        let path = temp_file(
+            "boards",
+            ".X.\nXXX\n.X.\n\n\nX....\n.....\n.....\n.....\n.....\n",
+        );
+        let solutions = Puzzle::solve_file(&path);
+        fs::remove_file(&path).unwrap();
+
+        let mut center = vec![false; 9];
+        center[4] = true;
+        assert_eq!(solutions.unwrap(), vec![Some(center), None]);
+    }
+
+    #[test]
+    fn test_solve_file_invalid_board() {
+        let path = temp_file("invalid", "X.\n.X\n\nX.\nXo\n");
+        let err = Puzzle::solve_file(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "Board 1: Invalid cell 'o' at row 1, column 1"
+        );
+    }
+
+    #[rstest]
+    #[case(5, 5, 23, 2)]
+    #[case(4, 4, 12, 4)]
+    #[case(3, 3, 9, 0)]
+    fn test_board_analysis(
+        #[case] width: usize,
+        #[case] height: usize,
+        #[case] rank: usize,
+        #[case] nullity: usize,
+    ) {
+        assert_eq!(
+            board_analysis(width, height),
+            BoardAnalysis {
+                rank,
+                nullity,
+                quiet_patterns: Some(1 << nullity),
+                solvable_configurations: Some(1 << rank),
+            }
+        );
+    }
+
+    #[rstest]
+    // Pressing either cell of 1x2 lights both, the empty board needs no press
+    #[case::pair(2, 1, vec![1, 1])]
+    // The toggle matrices are invertible, so every board has exactly one
+    // solution and k presses clear binomial(n, k) boards
+    #[case::square(2, 2, vec![1, 4, 6, 4, 1])]
+    #[case::three(3, 3, vec![1, 9, 36, 84, 126, 126, 84, 36, 9, 1])]
+    fn test_min_press_histogram(
+        #[case] width: usize,
+        #[case] height: usize,
+        #[case] expected: Vec<u64>,
+    ) {
+        assert_eq!(min_press_histogram(width, height), expected);
+    }
+
+    #[rstest]
+    // Every 3x3 board has exactly one solution, so k presses give binomial(9, k)
+    // different boards
+    #[case(0, 1)]
+    #[case(1, 9)]
+    #[case(2, 36)]
+    #[case(3, 84)]
+    fn test_boards_with_min_presses(#[case] k: usize, #[case] expected: usize) {
+        let boards = boards_with_min_presses(3, 3, k);
+        assert_eq!(boards.len(), expected);
+        assert_eq!(boards.iter().unique().count(), expected);
+        for board in boards {
+            let puzzle = Puzzle::new(3, 3, board);
+            let presses = puzzle.minimum_presses().unwrap();
+            assert_eq!(presses.iter().filter(|x| x.value != 0).count(), k);
+        }
+    }
+
+    #[test]
+    fn test_boards_with_min_presses_matches_histogram() {
+        // The 4x4 board has quiet patterns, so some boards are reached by several
+        // press sets of different sizes
+        let histogram = min_press_histogram(4, 4);
+        for (k, &count) in histogram.iter().enumerate().take(4) {
+            assert_eq!(boards_with_min_presses(4, 4, k).len() as u64, count);
+        }
+    }
+
+    #[test]
+    fn test_min_press_histogram_counts_solvable_boards() {
+        // Boards with quiet patterns have several solutions, but still every
+        // solvable board is counted once
+        let histogram = min_press_histogram(4, 4);
+        assert_eq!(histogram.iter().sum::<u64>(), 1 << 12);
+        assert_eq!(histogram[..2], [1, 16]);
+    }
+
+    #[rstest]
+    #[case::pair(2, 1, 2)]
+    #[case::square(2, 2, 6)]
+    #[case::three(3, 3, 102)]
+    #[case::empty(0, 3, 1)]
+    #[case::empty_rows(3, 0, 1)]
+    fn test_distinct_solvable_boards(
+        #[case] width: usize,
+        #[case] height: usize,
+        #[case] expected: u64,
+    ) {
+        // A 1x2 board can only reach the empty and the full board. The 2x2 and
+        // 3x3 boards are fully solvable, which leaves the number of two colorings
+        // of the square grid up to rotation and reflection.
+        assert_eq!(distinct_solvable_boards(width, height), expected);
+    }
+
+    #[test]
+    fn test_symmetries() {
+        let cross: Puzzle = "..X..\n..X..\nXXXXX\n..X..\n..X..".parse().unwrap();
+        assert_eq!(cross.symmetries(), Symmetry::ALL.to_vec());
+
+        let corner: Puzzle = "XX.\nX..\n...".parse().unwrap();
+        assert_eq!(
+            corner.symmetries(),
+            vec![Symmetry::Identity, Symmetry::FlipDiagonal]
+        );
+
+        let asymmetric: Puzzle = "XX.\n...\nX..".parse().unwrap();
+        assert_eq!(asymmetric.symmetries(), vec![Symmetry::Identity]);
+
+        // Rotating a non-square board by 90 degrees changes its shape
+        let empty = Puzzle::new(3, 2, vec![false; 6]);
+        assert_eq!(empty.symmetries().len(), 4);
+    }
+
+    #[test]
+    fn test_symmetries_empty_board() {
+        assert_eq!(
+            Puzzle::new(0, 0, vec![]).symmetries(),
+            Symmetry::ALL.to_vec()
+        );
+        assert_eq!(Puzzle::new(0, 3, vec![]).symmetries().len(), 4);
+    }
+
+    #[test]
+    fn test_graph_puzzle_path() {
+        // Path P5 with every light on, e.g. pressing vertices 0 and 3 clears it
+        let adjacency = vec![vec![1], vec![0, 2], vec![1, 3], vec![2, 4], vec![3]];
+        let mut puzzle = GraphPuzzle::new(adjacency, vec![true; 5]);
+        let solution = puzzle.solve().unwrap();
+        for vertex in (0..5).filter(|&vertex| solution[vertex]) {
+            puzzle.press(vertex);
+        }
+        assert_eq!(puzzle.lit, vec![false; 5]);
+    }
+
+    #[test]
+    fn test_graph_puzzle_complete() {
+        // Every vertex of K4 toggles all lights, so only the empty and the full
+        // board can be cleared
+        let adjacency = (0..4)
+            .map(|vertex| (0..4).filter(|&other| other != vertex).collect_vec())
+            .collect_vec();
+        let full = GraphPuzzle::new(adjacency.clone(), vec![true; 4]);
+        let solution = full.solve().unwrap();
+        assert_eq!(solution.iter().filter(|&&pressed| pressed).count(), 1);
+        let single = GraphPuzzle::new(adjacency, vec![true, false, false, false]);
+        assert_eq!(single.solve(), None);
+    }
+
+    #[rstest]
+    #[case(3, 2)]
+    #[case(4, 4)]
+    #[case(0, 3)]
+    fn test_distinct_solvable_boards_brute_force(#[case] width: usize, #[case] height: usize) {
+        // Collect every board reachable from the empty board, keeping the
+        // smallest of its symmetric images
+        let permutations = dihedral_permutations(width, height);
+        let n_cells = width * height;
+        let canonical = (0..1u32 << n_cells)
+            .map(|presses| {
+                let mut board = Puzzle::new(width, height, vec![false; n_cells]);
+                for idx in (0..n_cells).filter(|idx| presses >> idx & 1 == 1) {
+                    board.press(idx / width, idx % width);
+                }
+                permutations
+                    .iter()
+                    .map(|permutation| {
+                        permutation
+                            .iter()
+                            .map(|&image| board.initial[image])
+                            .collect_vec()
+                    })
+                    .min()
+                    .unwrap()
+            })
+            .unique()
+            .count();
+        assert_eq!(distinct_solvable_boards(width, height), canonical as u64);
+    }
+
+    #[test]
+    fn test_press() {
+        let mut puzzle = Puzzle::new(3, 3, vec![false; 9]);
+        puzzle.press(1, 1);
+        assert_eq!(puzzle, ".X.\nXXX\n.X.".parse().unwrap());
+        // Pressing again toggles the cells back
+        puzzle.press(1, 1);
+        assert_eq!(puzzle.initial, vec![false; 9]);
+    }
+
+    #[test]
+    fn test_press_edges() {
+        let mut puzzle = Puzzle::new(3, 2, vec![false; 6]);
+        puzzle.press(0, 2);
+        assert_eq!(puzzle, ".XX\n..X".parse().unwrap());
+        // Out of range presses are ignored
+        puzzle.press(2, 0);
+        puzzle.press(0, 3);
+        assert_eq!(puzzle, ".XX\n..X".parse().unwrap());
+    }
+
+    #[test]
+    fn test_press_matches_solve() {
+        // Applying the presses of a solution turns all lights off
+        let mut puzzle: Puzzle = "XX.\n.X.\n..X".parse().unwrap();
+        let solution = puzzle.solve().unwrap();
+        for (idx, _) in solution.iter().enumerate().filter(|(_, pressed)| **pressed) {
+            puzzle.press(idx / 3, idx % 3);
+        }
+        assert_eq!(puzzle.initial, vec![false; 9]);
+    }
+
+    fn brute_force_solve(puzzle: &Puzzle) -> Option<Vec<bool>> {
+        // Try all 2^(w*h) press combinations, the boards being bitmasks of cells
+        let (width, height) = (puzzle.width, puzzle.height);
+        let n_cells = width * height;
+        assert!(
+            n_cells <= 16,
+            "Brute force is only feasible for small boards"
+        );
+        let effects = (0..n_cells)
+            .map(|button| {
+                let mut board = Puzzle::new(width, height, vec![false; n_cells]);
+                board.press(button / width, button % width);
+                (0..n_cells)
+                    .filter(|&cell| board.initial[cell])
+                    .fold(0u32, |mask, cell| mask | 1 << cell)
+            })
+            .collect_vec();
+        let lit = (0..n_cells)
+            .filter(|&cell| puzzle.initial[cell])
+            .fold(0u32, |mask, cell| mask | 1 << cell);
+        (0..1u32 << n_cells)
+            .find(|presses| {
+                (0..n_cells)
+                    .filter(|button| presses >> button & 1 == 1)
+                    .fold(lit, |board, button| board ^ effects[button])
+                    == 0
+            })
+            .map(|presses| {
+                (0..n_cells)
+                    .map(|button| presses >> button & 1 == 1)
+                    .collect_vec()
+            })
+    }
+
+    fn small_puzzle() -> impl Strategy<Value = Puzzle> {
+        // Boards of up to 4x4 cells with random lights
+        (1..5usize, 1..5usize).prop_flat_map(|(width, height)| {
+            prop::collection::vec(any::<bool>(), width * height)
+                .prop_map(move |initial| Puzzle::new(width, height, initial))
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+        #[test]
+        fn test_solve_matches_brute_force(puzzle in small_puzzle()) {
+            let target = vec![false; puzzle.initial.len()];
+            let brute_force = brute_force_solve(&puzzle);
+            for solution in [matrix_solve(&puzzle), puzzle.solve()] {
+                prop_assert_eq!(solution.is_some(), brute_force.is_some());
+                if let Some(presses) = solution {
+                    prop_assert!(puzzle.verify(&presses, &target));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::finite_field::GFElement;
+use crate::linalg::Matrix;
+use itertools::{EitherOrBoth, Itertools};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SparseMatrix {
+    // A matrix storing only the non-zero elements of each row as (column, element)
+    // pairs, sorted by column. Toggle matrices of large boards are mostly zeros,
+    // which this avoids storing.
+    n_cols: usize,
+    modulus: i32,
+    rows: Vec<Vec<(usize, GFElement)>>,
+}
+
+impl SparseMatrix {
+    pub fn new(n_cols: usize, modulus: i32, rows: Vec<Vec<(usize, GFElement)>>) -> Self {
+        // Create a sparse matrix, zero elements are dropped and every row is
+        // sorted by column
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                assert!(
+                    row.iter().all(|(col_idx, _)| *col_idx < n_cols),
+                    "Column index out of bounds"
+                );
+                assert!(
+                    row.iter().all(|(_, element)| element.modulus == modulus),
+                    "Matrix has mixed moduli"
+                );
+                row.into_iter()
+                    .filter(|(_, element)| element.value != 0)
+                    .sorted_by_key(|(col_idx, _)| *col_idx)
+                    .collect_vec()
+            })
+            .collect_vec();
+        SparseMatrix {
+            n_cols,
+            modulus,
+            rows,
+        }
+    }
+
+    pub fn from_dense(matrix: &Matrix) -> Self {
+        let modulus = matrix
+            .modulus()
+            .expect("Matrix should have at least one element");
+        let rows = matrix
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .copied()
+                    .enumerate()
+                    .filter(|(_, element)| element.value != 0)
+                    .collect_vec()
+            })
+            .collect_vec();
+        SparseMatrix::new(matrix.n_cols(), modulus, rows)
+    }
+
+    pub fn to_dense(&self) -> Matrix {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut dense_row = vec![GFElement::new(0, self.modulus); self.n_cols];
+                for (col_idx, element) in row.iter() {
+                    dense_row[*col_idx] = *element;
+                }
+                dense_row
+            })
+            .collect_vec();
+        Matrix::new(rows)
+    }
+
+    pub fn nonzero_count(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).sum()
+    }
+
+    pub fn to_rref(&self) -> SparseMatrix {
+        // Convert the matrix to reduced row echelon form, producing the same
+        // canonical form as Matrix::to_rref. Rows only grow where eliminating a
+        // column fills in new non-zero elements.
+        let n_rows = self.rows.len();
+        let mut new_rows = self.rows.clone();
+
+        let mut pivot_row_idx = 0;
+        for col_idx in 0..self.n_cols {
+            if pivot_row_idx == n_rows {
+                break;
+            }
+
+            // All earlier columns are zero below the pivot row, so a row holding
+            // this column starts with it
+            let Some(found_row_idx) = (pivot_row_idx..n_rows).find(|&row_idx| {
+                new_rows[row_idx]
+                    .first()
+                    .is_some_and(|(first_col_idx, _)| *first_col_idx == col_idx)
+            }) else {
+                continue;
+            };
+            new_rows.swap(pivot_row_idx, found_row_idx);
+
+            // Scale the pivot row by its leading element
+            let scale = new_rows[pivot_row_idx][0].1;
+            for (_, element) in new_rows[pivot_row_idx].iter_mut() {
+                *element = *element / scale;
+            }
+
+            // Zero the column in all other rows
+            let pivot_row = new_rows[pivot_row_idx].clone();
+            for (other_row_idx, other_row) in new_rows.iter_mut().enumerate() {
+                if other_row_idx == pivot_row_idx {
+                    continue;
+                }
+                if let Ok(position) = other_row.binary_search_by_key(&col_idx, |(idx, _)| *idx) {
+                    let scale = other_row[position].1;
+                    *other_row = subtract_scaled(other_row, &pivot_row, scale);
+                }
+            }
+
+            pivot_row_idx += 1;
+        }
+
+        SparseMatrix {
+            n_cols: self.n_cols,
+            modulus: self.modulus,
+            rows: new_rows,
+        }
+    }
+}
+
+fn subtract_scaled(
+    row: &[(usize, GFElement)],
+    pivot_row: &[(usize, GFElement)],
+    scale: GFElement,
+) -> Vec<(usize, GFElement)> {
+    // Merge the sorted rows into row - scale * pivot_row, dropping elements that
+    // become zero
+    row.iter()
+        .merge_join_by(pivot_row.iter(), |(col_idx, _), (pivot_col_idx, _)| {
+            col_idx.cmp(pivot_col_idx)
+        })
+        .map(|pair| match pair {
+            EitherOrBoth::Both((col_idx, element), (_, pivot_element)) => {
+                (*col_idx, *element - scale * *pivot_element)
+            }
+            EitherOrBoth::Left((col_idx, element)) => (*col_idx, *element),
+            EitherOrBoth::Right((col_idx, pivot_element)) => (*col_idx, -(scale * *pivot_element)),
+        })
+        .filter(|(_, element)| element.value != 0)
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseMatrix;
+    use crate::finite_field::GFElement;
+    use crate::linalg::Matrix;
+    use crate::puzzle::Puzzle;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_dense_round_trip() {
+        let matrix = Matrix::random(4, 5, 3, 9);
+        let sparse = SparseMatrix::from_dense(&matrix);
+        assert_eq!(
+            sparse.nonzero_count(),
+            matrix
+                .rows
+                .iter()
+                .flatten()
+                .filter(|x| x.value != 0)
+                .count()
+        );
+        assert_eq!(sparse.to_dense(), matrix);
+    }
+
+    #[test]
+    fn test_new_drops_zeros_and_sorts() {
+        let sparse = SparseMatrix::new(
+            3,
+            5,
+            vec![vec![(2, GFElement::new(4, 5)), (0, GFElement::new(5, 5))]],
+        );
+        assert_eq!(format!("{}", sparse.to_dense()), "0 0 4");
+        assert_eq!(sparse.nonzero_count(), 1);
+    }
+
+    #[test]
+    fn test_rref_matches_dense() {
+        for (seed, modulus) in [(1, 2), (2, 5), (3, 7)] {
+            let matrix = Matrix::random(6, 8, modulus, seed);
+            let sparse_rref = SparseMatrix::from_dense(&matrix).to_rref();
+            assert_eq!(sparse_rref.to_dense(), matrix.to_rref());
+        }
+    }
+
+    #[test]
+    fn test_toggle_matrix_solves_like_dense() {
+        for seed in 0..4 {
+            let initial = (0..36).map(|idx| (idx * 11 + seed) % 3 == 0).collect_vec();
+            let matrix = Puzzle::new(6, 6, initial).to_matrix();
+            let sparse_rref = SparseMatrix::from_dense(&matrix).to_rref();
+            assert_eq!(sparse_rref.to_dense().solution(), matrix.solution());
+        }
+    }
+}
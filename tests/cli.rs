@@ -0,0 +1,62 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run_solver(args: &[&str], board: &str) -> Output {
+    // Run the binary with the board piped to stdin
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lights_out_solver"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Binary should start");
+    child
+        .stdin
+        .take()
+        .expect("Stdin should be piped")
+        .write_all(board.as_bytes())
+        .expect("Board should be written");
+    child.wait_with_output().expect("Binary should finish")
+}
+
+#[test]
+fn test_solves_board() {
+    let output = run_solver(&[], ".X.\nXXX\n.X.\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "...\n.X.\n...\n");
+}
+
+#[test]
+fn test_solves_board_with_modulus() {
+    let output = run_solver(&["--modulus", "3"], ".X.\nXXX\n.X.\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "...\n.2.\n...\n");
+}
+
+#[test]
+fn test_unsolvable_board() {
+    let output = run_solver(&[], "X....\n.....\n.....\n.....\n.....\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be solved"));
+}
+
+#[test]
+fn test_invalid_board() {
+    let output = run_solver(&[], "X.\nXo\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_analyze() {
+    let output = run_solver(&["analyze", "5", "5"], "");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.lines().any(|line| line == "nullity: 2"));
+    assert!(stdout.lines().any(|line| line == "quiet patterns: 4"));
+}
+
+#[test]
+fn test_analyze_invalid_size() {
+    let output = run_solver(&["analyze", "5", "x"], "");
+    assert_eq!(output.status.code(), Some(2));
+}